@@ -0,0 +1,51 @@
+// Times generating a large maze and the cell/room lookups the monster systems do every frame.
+// No benchmark framework, just Instant, so it runs with plain `cargo bench --bench maze_lookup`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use maze::maze::maze_config::MazeConfig;
+use maze::maze::maze_layout::MazeLayout;
+use maze::maze::maze_room::ROOM_SETTINGS_NAMES;
+use maze::position::Position;
+use maze::random::MazeSeed;
+
+const MAZE_SIZE: i32 = 200;
+const GENERATION_RUNS: u32 = 5;
+const FRAMES: u32 = 10_000;
+// roughly what one frame of monster updates asks for: the player's and monster's rooms and their neighbouring cells
+const LOOKUPS_PER_FRAME: u32 = 16;
+
+fn main() {
+    let config = MazeConfig { maze_x: MAZE_SIZE, maze_y: MAZE_SIZE, ..Default::default() };
+    let mut rng = MazeSeed(0x5EED).create_rng();
+
+    let mut generation_time = Duration::ZERO;
+    let mut layout = MazeLayout::default();
+    for _ in 0..GENERATION_RUNS {
+        let start = Instant::now();
+        layout = MazeLayout::generate(&config, ROOM_SETTINGS_NAMES.len(), &config.algorithm, &mut rng);
+        config.braid.braid(&mut layout, &mut rng);
+        generation_time += start.elapsed();
+    }
+    println!("generate {}x{}: {:?} per maze", MAZE_SIZE, MAZE_SIZE, generation_time / GENERATION_RUNS);
+
+    let positions: Vec<Position> = (0..FRAMES * LOOKUPS_PER_FRAME)
+        .map(|_| Position::new_from_i32(rng.gen_range(0..MAZE_SIZE), rng.gen_range(0..MAZE_SIZE)))
+        .collect();
+
+    let start = Instant::now();
+    for position in &positions {
+        black_box(layout.get_cell(black_box(position)));
+    }
+    let cell_time = start.elapsed();
+    println!("get_cell: {:?} per frame of {} lookups", cell_time / FRAMES, LOOKUPS_PER_FRAME);
+
+    let start = Instant::now();
+    for position in &positions {
+        black_box(layout.get_room_number_for_position(black_box(*position)));
+    }
+    let room_time = start.elapsed();
+    println!("get_room_number_for_position: {:?} per frame of {} lookups", room_time / FRAMES, LOOKUPS_PER_FRAME);
+}
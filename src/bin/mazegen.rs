@@ -0,0 +1,69 @@
+// Generates a maze without opening a window and prints it to stdout.
+// Takes the same flags as the game for the maze itself (see MazeConfig and MazeSeed), plus --format,
+// and --save <path> to also write a file the game can play with --load.
+// With --import <path> it reads a hand drawn level instead of generating one, handy for checking a level for mistakes.
+//
+// cargo run --bin mazegen -- --maze-x 12 --maze-y 8 --algorithm prim --seed B79S-WWBZ-J --format svg > maze.svg
+use std::process::ExitCode;
+
+use maze::maze::maze_config::MazeConfig;
+use maze::maze::maze_export::{export_layout, ExportFormat};
+use maze::maze::maze_import::import_ascii_layout_from_file;
+use maze::maze::maze_layout::MazeLayout;
+use maze::maze::maze_room::ROOM_SETTINGS_NAMES;
+use maze::maze::maze_save::MazeSave;
+use maze::random::MazeSeed;
+
+const USAGE: &str = "Usage: mazegen [--format ascii|json|svg] [--seed <code>] [--maze-x <width>] [--maze-y <height>] [--algorithm <name>] [--config <path>] [--import <path>] [--save <path>]
+Algorithms: growing-tree-newest, growing-tree-random, growing-tree-oldest, growing-tree-mixed, prim, kruskal, wilson, eller, recursive-division";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        },
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    let config = MazeConfig::load_from_args(args.iter().cloned())?;
+    let seed = MazeSeed::from_args_or_env(args)?.unwrap_or_else(MazeSeed::random);
+    let format = match args.iter().position(|arg| arg == "--format") {
+        Some(index) => args.get(index + 1).ok_or("--format needs a value")?.parse()?,
+        None => ExportFormat::Ascii
+    };
+
+    // so a random layout can always be generated again
+    eprintln!("Maze seed: {}", seed);
+
+    let mut rng = seed.create_rng();
+    let layout = match args.iter().position(|arg| arg == "--import") {
+        Some(index) => {
+            let path = args.get(index + 1).ok_or("--import needs a path")?;
+            import_ascii_layout_from_file(path, &config, &ROOM_SETTINGS_NAMES, &mut rng)?
+        },
+        None => {
+            let mut layout = MazeLayout::generate(&config, ROOM_SETTINGS_NAMES.len(), &config.algorithm, &mut rng);
+            config.braid.braid(&mut layout, &mut rng);
+            layout
+        }
+    };
+
+    if let Some(index) = args.iter().position(|arg| arg == "--save") {
+        let path = args.get(index + 1).ok_or("--save needs a path")?;
+        MazeSave::from_layout(&layout, &ROOM_SETTINGS_NAMES, Some(seed)).save_to_file(path)?;
+    }
+
+    Ok(export_layout(&layout, format, &ROOM_SETTINGS_NAMES, Some(seed)))
+}
@@ -0,0 +1,105 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use bevy::prelude::*;
+
+use crate::maze::{maze_layout::MazeLayout, room_links::RoomLinks};
+use crate::pathfinding::{get_neighbours, PathfindingOptions};
+use crate::position::Position;
+
+/**
+ * How far every cell is from one target cell, so any number of agents can find their way to the target by
+ * stepping to whichever neighbouring cell is closest, instead of each running their own search.
+ * Built with Dijkstra over the cells, using the same neighbour and edge cost rules as the A* in pathfinding.
+ */
+#[derive(Resource, Default)]
+pub struct FlowField {
+    size_x: i32,
+    size_y: i32,
+    target: Option<Position>,
+    options: PathfindingOptions,
+    // None for cells that can't reach the target
+    distances: Vec<Option<usize>>
+}
+
+impl FlowField {
+    pub fn new(options: PathfindingOptions) -> Self {
+        FlowField { options, ..default() }
+    }
+
+    pub fn get_target(&self) -> Option<Position> {
+        self.target
+    }
+
+    pub fn get_distance(&self, position: &Position) -> Option<usize> {
+        self.get_index(position).and_then(|index| self.distances[index])
+    }
+
+    // the neighbour to step to from the given cell, None at the target or where the target can't be reached
+    pub fn get_next_cell(&self, layout: &MazeLayout, room_links: &RoomLinks, position: Position) -> Option<Position> {
+        if self.get_distance(&position)? == 0 {
+            return None;
+        }
+        get_neighbours(layout, room_links, position, &self.options).into_iter()
+            .filter_map(|(next, cost)| Some((next, self.get_distance(&next)? + cost)))
+            .min_by_key(|(_, through_next)| *through_next)
+            .map(|(next, _)| next)
+    }
+
+    pub fn rebuild(&mut self, layout: &MazeLayout, room_links: &RoomLinks, target: Position) {
+        self.size_x = layout.size_x;
+        self.size_y = layout.size_y;
+        self.target = Some(target);
+        self.distances = vec![None; (self.size_x * self.size_y) as usize];
+
+        let Some(index) = self.get_index(&target) else { return };
+        self.distances[index] = Some(0);
+        self.spread(layout, room_links, vec![target]);
+    }
+
+    // A door opening can only make cells closer to the target, so instead of rebuilding everything
+    // this spreads the shorter distances out from both sides of the door
+    pub fn open_between(&mut self, layout: &MazeLayout, room_links: &RoomLinks, position1: Position, position2: Position) {
+        if self.target.is_none() {
+            return;
+        }
+        self.spread(layout, room_links, vec![position1, position2]);
+    }
+
+    // a door closing can make cells further away, which would mean undoing the spread, so just start over
+    pub fn close_between(&mut self, layout: &MazeLayout, room_links: &RoomLinks) {
+        if let Some(target) = self.target {
+            self.rebuild(layout, room_links, target);
+        }
+    }
+
+    // Dijkstra from the given cells, only ever lowering distances
+    fn spread(&mut self, layout: &MazeLayout, room_links: &RoomLinks, starts: Vec<Position>) {
+        let mut open: BinaryHeap<Reverse<(usize, i32, i32)>> = starts.iter()
+            .filter_map(|position| Some(Reverse((self.get_distance(position)?, position.x as i32, position.y as i32))))
+            .collect();
+
+        while let Some(Reverse((distance, x, y))) = open.pop() {
+            let position = Position::new_from_i32(x, y);
+            if self.get_distance(&position).is_some_and(|best| best < distance) {
+                continue;
+            }
+            for (next, cost) in get_neighbours(layout, room_links, position, &self.options) {
+                let Some(next_index) = self.get_index(&next) else { continue };
+                if self.distances[next_index].is_some_and(|best| best <= distance + cost) {
+                    continue;
+                }
+                self.distances[next_index] = Some(distance + cost);
+                open.push(Reverse((distance + cost, next.x as i32, next.y as i32)));
+            }
+        }
+    }
+
+    fn get_index(&self, position: &Position) -> Option<usize> {
+        let (x, y) = (position.x as i32, position.y as i32);
+        if x < 0 || y < 0 || x >= self.size_x || y >= self.size_y {
+            return None;
+        }
+        Some((y * self.size_x + x) as usize)
+    }
+}
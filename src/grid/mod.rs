@@ -1,3 +0,0 @@
-pub mod grid;
-pub mod grid_location;
-pub mod dirty_grid_event;
\ No newline at end of file
@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+pub mod maze;
+pub mod position;
+pub mod player;
+pub mod random;
+pub mod consts;
+pub mod physics;
+pub mod game_states;
+pub mod apply_render_layers_to_children;
+pub mod assets;
+pub mod monster;
+pub mod character;
+pub mod pathfinding_node;
+pub mod pathfinding;
+pub mod flow_field;
+pub mod noise;
+pub mod main_menu;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameLoadSet;
@@ -1,73 +1,102 @@
-use bevy::{prelude::*, reflect::TypeRegistry, render::{camera::Viewport, mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes}, primitives::Aabb, view::RenderLayers}};
+use bevy::{prelude::*, reflect::TypeRegistry, render::{camera::Viewport, view::RenderLayers}};
 
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use maze::{maze_assets::MazeAssets, maze_door::{door_open_system, MazeDoor}};
-use monster::{monster::MonsterPlugin, monster_assets::MonsterAssets, monster_events::MonsterReachedPlayer};
-use position::Position;
-use rand::SeedableRng;
-use rand_chacha::ChaCha8Rng;
-
-use crate::maze::maze::Maze;
-
-use player::{player::{LogicalPlayer, PlayerPlugin, WorldModelCamera}, player_events::PlayerCellChangeEvent};
-use random::Random;
-use game_states::GameState;
-use physics::physics::PhysicsPlugin;
-
-mod maze;
-mod position;
-mod player;
-mod random;
-mod consts;
-mod physics;
-mod game_states;
-mod apply_render_layers_to_children;
-mod assets;
-mod monster;
-mod character;
-mod grid;
-mod pathfinding_node;
+use maze::maze::{maze_assets::MazeAssets, maze_door::door_open_system, maze_layout::MazeLayout, maze_config::MazeConfig, maze_room::{MazeRoomSettings, ROOM_SETTINGS_NAMES}, maze_save::{LoadedMazeLayout, MazeSave, DEFAULT_SAVE_PATH}, maze_import::import_ascii_layout_from_file, maze_events::{DoorStateChanged, RoomMerged}};
+use maze::monster::{monster::MonsterPlugin, monster_assets::MonsterAssets, monster_catalog::MonsterCatalog, monster_events::{MonsterReachedPlayer, PlayerLost, PlayerSeen}, monster_perception::MonsterPerception};
+use maze::noise::NoiseEvent;
+use maze::position::Position;
+
+use maze::maze::maze::Maze;
+
+use maze::player::{player::{LogicalPlayer, PlayerPlugin, WorldModelCamera}, player_events::PlayerCellChangeEvent};
+use maze::random::{MazeSeed, Random};
+use maze::game_states::GameState;
+use maze::main_menu::main_menu::{open_main_menu, MainMenuPlugin};
+use maze::physics::physics::PhysicsPlugin;
+use maze::{consts, GameLoadSet};
 
 #[derive(Component)]
 struct TopDownCamera;
 
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct GameStartSet;
 
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct GameRunSet;
-
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct GameLoadSet;
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut config = MazeConfig::load_from_args(args.iter().cloned()).unwrap_or_else(|error| panic!("{}", error));
+    let monster_catalog = MonsterCatalog::load_from_args(&args).unwrap_or_else(|error| panic!("{}", error));
+    let mut seed = MazeSeed::from_args_or_env(&args).unwrap_or_else(|error| panic!("{}", error));
+    let saved_maze = MazeSave::load_from_args(&args).unwrap_or_else(|error| panic!("{}", error));
 
+    // a saved maze brings its seed unless one was given
+    let mut loaded_layout = saved_maze.map(|saved_maze| {
+        if seed.is_none() {
+            seed = saved_maze.get_seed().unwrap_or_else(|error| panic!("{}", error));
+        }
+        saved_maze.to_layout(&ROOM_SETTINGS_NAMES).unwrap_or_else(|error| panic!("{}", error))
+    });
+    if let Some(index) = args.iter().position(|arg| arg == "--import") {
+        let path = args.get(index + 1).unwrap_or_else(|| panic!("--import needs a path"));
+        // the seed only decides the wall lights here, but it still has to be the one shown in game
+        let mut rng = seed.get_or_insert_with(MazeSeed::random).create_rng();
+        let layout = import_ascii_layout_from_file(path, &config, &ROOM_SETTINGS_NAMES, &mut rng).unwrap_or_else(|error| panic!("{}", error));
+        loaded_layout = Some(layout);
+    }
+    // and either one brings its own size
+    if let Some(layout) = &loaded_layout {
+        config.maze_x = layout.size_x;
+        config.maze_y = layout.size_y;
+    }
 
-fn main() {
-    App::new()
+    let mut app = App::new();
+    app
         .add_plugins((
             DefaultPlugins,
             WorldInspectorPlugin::new(),
         ))
         .insert_state(GameState::LoadingAssets)
-        .add_systems(OnEnter(GameState::LoadingAssets), (MazeAssets::load_assets, MonsterAssets::load_assets, setup_rng).chain().in_set(GameLoadSet))
-        .add_systems(OnEnter(GameState::Initialize), generate_maze)
-        .add_systems(OnEnter(GameState::InGame), render_game)
+        .insert_resource(Time::<Fixed>::from_hz(config.tick_rate))
+        .insert_resource(config)
+        .insert_resource(monster_catalog)
+        .add_systems(OnEnter(GameState::LoadingAssets), (MazeAssets::load_assets, MonsterAssets::load_assets, open_main_menu).chain().in_set(GameLoadSet))
+        .add_systems(OnEnter(GameState::Initialize), (
+            setup_rng,
+            generate_maze.run_if(not(resource_exists::<LoadedMazeLayout>)),
+            load_maze.run_if(resource_exists::<LoadedMazeLayout>)
+        ).chain())
+        .add_systems(OnEnter(GameState::InGame), (render_game, show_seed_hud, show_seen_hud))
         .add_plugins(PlayerPlugin)
-        .add_systems(Update, (move_minimap_position, recalculate_skinned_aabb).run_if(in_state(GameState::InGame)))
-        .add_systems(Update, (on_player_cell_change_door_check, door_open_system).run_if(in_state(GameState::InGame)))
+        .add_plugins(MainMenuPlugin)
+        .add_systems(Update, move_minimap_position.run_if(in_state(GameState::InGame)))
+        .add_systems(FixedUpdate, door_open_system.run_if(in_state(GameState::InGame)))
+        .add_systems(Update, save_maze_on_key.run_if(in_state(GameState::InGame)))
+        .add_systems(Update, update_seen_hud.run_if(in_state(GameState::InGame)))
         .add_systems(Update, (on_player_cell_change_win_check, on_monster_reached_player).chain().run_if(in_state(GameState::InGame)))
         .add_plugins(PhysicsPlugin)
         .add_plugins(MonsterPlugin)
-        .register_type::<Position>()
-        .run();
+        .add_event::<RoomMerged>()
+        .add_event::<DoorStateChanged>()
+        .add_event::<NoiseEvent>()
+        .register_type::<Position>();
+
+    // no seed means setup_rng picks one
+    if let Some(seed) = seed {
+        app.insert_resource(seed);
+    }
+    if let Some(layout) = loaded_layout {
+        app.insert_resource(LoadedMazeLayout(layout));
+    }
+
+    app.run();
 }
 
+// the seed is settled by now, from the command line, the main menu or a loaded maze
 fn setup_rng(
     mut commands: Commands,
-    mut next_state: ResMut<NextState<GameState>>
+    seed: Option<Res<MazeSeed>>
 ) {
-    let rng = ChaCha8Rng::from_entropy();
-    commands.insert_resource(Random(rng));
-    next_state.set(GameState::Initialize);
+    let seed = seed.map(|seed| *seed).unwrap_or_else(MazeSeed::random);
+    info!("Maze seed: {}", seed);
+    commands.insert_resource(Random(seed.create_rng()));
+    commands.insert_resource(seed);
 }
 
 // Render everything
@@ -75,35 +104,131 @@ fn render_game(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut maze: ResMut<Maze>,
+    config: Res<MazeConfig>,
 ) {
     let floors = generate_empty_object_with_name(&mut commands, "floors");
-    maze.render_maze(&mut commands, &mut meshes, floors);
-    add_lights(&mut commands);
+    maze.render_maze(&mut commands, &mut meshes, floors, config.maze_scale);
+    add_lights(&mut commands, &config);
     add_top_view_camera(commands);
 }
 
 fn generate_maze(
     mut commands: Commands, 
     mut rng: ResMut<Random>, 
+    config: Res<MazeConfig>,
     maze_assets: Res<MazeAssets>, 
-    materials: ResMut<'_, Assets<StandardMaterial>>,
-    mut next_state: ResMut<NextState<GameState>>
+    mut materials: ResMut<'_, Assets<StandardMaterial>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut room_merged_writer: EventWriter<RoomMerged>
 ) {
     // create a maze
-    let mut maze = Maze::new(consts::MAZE_X, consts::MAZE_Y);
-    maze.generate(&mut rng, maze_assets, materials);
+    let room_settings = MazeRoomSettings::create_all_room_settings(&maze_assets, &mut materials);
+    let mut layout = MazeLayout::generate(&config, room_settings.len(), &config.algorithm, &mut rng.0);
+    config.braid.braid(&mut layout, &mut rng.0);
+    room_merged_writer.send_batch(layout.take_room_merges());
+    commands.insert_resource(layout.take_room_links());
+    let maze = Maze::from_layout(layout, room_settings);
     commands.insert_resource(maze);
     next_state.set(GameState::InGame)
 }
 
-fn add_lights(commands: &mut Commands<'_, '_>) {
+// skips generation and builds the Maze from the layout given with --load or --import
+fn load_maze(
+    mut commands: Commands,
+    mut loaded_layout: ResMut<LoadedMazeLayout>,
+    maze_assets: Res<MazeAssets>,
+    mut materials: ResMut<'_, Assets<StandardMaterial>>,
+    mut next_state: ResMut<NextState<GameState>>
+) {
+    let room_settings = MazeRoomSettings::create_all_room_settings(&maze_assets, &mut materials);
+    let mut layout = std::mem::take(&mut loaded_layout.0);
+    commands.remove_resource::<LoadedMazeLayout>();
+    commands.insert_resource(layout.take_room_links());
+    commands.insert_resource(Maze::from_layout(layout, room_settings));
+    next_state.set(GameState::InGame)
+}
+
+// F5 writes the current maze out so it can be played again with --load
+fn save_maze_on_key(
+    key_input: Res<ButtonInput<KeyCode>>,
+    maze: Res<Maze>,
+    seed: Res<MazeSeed>
+) {
+    if !key_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+    let saved_maze = MazeSave::from_layout(maze.get_layout(), &maze.get_room_names(), Some(*seed));
+    match saved_maze.save_to_file(DEFAULT_SAVE_PATH) {
+        Ok(()) => info!("Saved maze to {}", DEFAULT_SAVE_PATH),
+        Err(error) => warn!("{}", error)
+    }
+}
+
+fn show_seed_hud(
+    mut commands: Commands<'_, '_>,
+    seed: Res<MazeSeed>,
+    main_camera_query: Query<Entity, With<WorldModelCamera>>
+) {
+    let player_camera = main_camera_query.single();
+    commands.spawn((
+        TextBundle::from(format!("Seed: {}", *seed)).with_style(
+            Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                right: Val::Px(8.0),
+                ..default()
+            }),
+        TargetCamera(player_camera),
+        Name::new("SeedHud")
+    ));
+}
+
+#[derive(Component)]
+struct SeenHud;
+
+fn show_seen_hud(
+    mut commands: Commands<'_, '_>,
+    main_camera_query: Query<Entity, With<WorldModelCamera>>
+) {
+    let player_camera = main_camera_query.single();
+    commands.spawn((
+        TextBundle::from("").with_style(
+            Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..default()
+            }),
+        TargetCamera(player_camera),
+        SeenHud,
+        Name::new("SeenHud")
+    ));
+}
+
+// warns the player while any monster can see them
+fn update_seen_hud(
+    mut seen: EventReader<PlayerSeen>,
+    mut lost: EventReader<PlayerLost>,
+    perceptions: Query<&MonsterPerception>,
+    mut hud: Query<&mut Text, With<SeenHud>>
+) {
+    if seen.read().count() + lost.read().count() == 0 {
+        return;
+    }
+    let is_seen = perceptions.iter().any(|perception| perception.sees_player());
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = String::from(if is_seen { "You've been seen!" } else { "" });
+    }
+}
+
+fn add_lights(commands: &mut Commands<'_, '_>, config: &MazeConfig) {
     // ambient light
     commands.insert_resource(AmbientLight {
         color: consts::GLOBAL_LIGHT_TINT,
         brightness: consts::GLOBAL_LIGHT_INTENSITY,
     });
 
-    let light_position: Vec2 = Vec2::splat(consts::MAZE_X as f32 * consts::MAZE_SCALE);
+    let light_position: Vec2 = Vec2::splat(config.maze_x as f32 * config.maze_scale);
 
     // directional light
     commands.spawn(DirectionalLightBundle {
@@ -161,30 +286,14 @@ fn move_minimap_position(
     camera_transform.translation = Vec3::new(player_transform.translation.x, consts::TOP_DOWN_CAMERA_HEIGHT, player_transform.translation.z);
 }
 
-fn on_player_cell_change_door_check(
-    mut event: EventReader<PlayerCellChangeEvent>,
-    mut door_query: Query<(&GlobalTransform, &mut MazeDoor)>,
-) {
-    for e in event.read() {
-        let player_position = e.0;
-        for (door_transform, mut maze_door) in door_query.iter_mut() {
-            let door_position = Position::get_from_transform(&door_transform.compute_transform(), consts::MAZE_SCALE);
-            if player_position == door_position {
-                maze_door.open_door(true);
-            } else if player_position == &door_position + maze_door.get_maze_direction().to_position_modifier() {
-                maze_door.open_door(false);
-            }
-        }
-    }
-}
-
 fn on_player_cell_change_win_check(
     mut commands: Commands<'_, '_>,
     mut event: EventReader<PlayerCellChangeEvent>,
     mut next_state: ResMut<NextState<GameState>>,
-    main_camera_query: Query<Entity, With<WorldModelCamera>>
+    main_camera_query: Query<Entity, With<WorldModelCamera>>,
+    maze: Res<Maze>,
 ) {
-    let winning_cell = Position::new((consts::MAZE_X - 1) as f32, (consts::MAZE_Y - 1) as f32);
+    let winning_cell = maze.get_layout().get_exit();
     for e in event.read() {
         let player_position = e.0;
         if player_position == winning_cell {
@@ -227,21 +336,3 @@ fn on_monster_reached_player(
         next_state.set(GameState::Lost);
     }
 }
-
-pub fn recalculate_skinned_aabb(
-    inverse_bindposes: Res<Assets<SkinnedMeshInverseBindposes>>,
-    mut query: Query<(&Name, &SkinnedMesh, &mut Aabb), Added<Aabb>>,
-) {
-    // HACK:
-    for (name, skinned_mesh, mut aabb) in query.iter_mut() {
-        let Some(inverse_bindposes) = inverse_bindposes.get(&skinned_mesh.inverse_bindposes) else {
-            continue;
-        };
-
-        let mut inverse_bindpose = inverse_bindposes[0]; // `0` probably won't work in all cases
-
-        // multiplying by `inverse_bindpose` seems to be the standard (https://github.com/KhronosGroup/glTF-Blender-IO/issues/1887)
-        aabb.center = (inverse_bindpose * aabb.center.extend(0.0)).into();
-        aabb.half_extents = (inverse_bindpose * aabb.half_extents.extend(0.0)).into();
-    }
-}
\ No newline at end of file
@@ -0,0 +1,175 @@
+use bevy::{input::{keyboard::{Key, KeyboardInput}, ButtonState}, prelude::*};
+
+use crate::{game_states::GameState, maze::maze_save::LoadedMazeLayout, player::player::WorldModelCamera, random::MazeSeed};
+
+pub struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedEntry>()
+            .add_systems(OnEnter(GameState::MainMenu), show_main_menu)
+            .add_systems(Update, enter_seed.run_if(in_state(GameState::MainMenu)))
+            .add_systems(OnExit(GameState::MainMenu), hide_main_menu);
+    }
+}
+
+#[derive(Component)]
+struct MainMenu;
+
+// What's been typed into the seed box so far, and why the last try was refused
+#[derive(Resource, Default)]
+struct SeedEntry {
+    text: String,
+    error: Option<String>
+}
+
+const SEED_TEXT_SECTION: usize = 1;
+const ERROR_TEXT_SECTION: usize = 2;
+
+// A maze from --load or --import already has its seed, so only a new maze gets the menu
+pub fn open_main_menu(
+    loaded_layout: Option<Res<LoadedMazeLayout>>,
+    mut next_state: ResMut<NextState<GameState>>
+) {
+    if loaded_layout.is_some() {
+        next_state.set(GameState::Initialize);
+    } else {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn show_main_menu(
+    mut commands: Commands,
+    mut seed_entry: ResMut<SeedEntry>,
+    seed: Option<Res<MazeSeed>>,
+    main_camera_query: Query<Entity, With<WorldModelCamera>>
+) {
+    // start with the seed from the command line if there was one
+    *seed_entry = SeedEntry { text: seed.map(|seed| seed.to_code()).unwrap_or_default(), error: None };
+
+    let player_camera = main_camera_query.single();
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("Type a seed and press Enter, or leave it empty for a random maze\n\nSeed: ", TextStyle::default()),
+            TextSection::new(seed_entry.text.clone(), TextStyle::default()),
+            TextSection::new("", TextStyle { color: Color::srgb(0.9, 0.2, 0.2), ..default() })
+        ]).with_style(
+            Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(40.0),
+                left: Val::Percent(30.0),
+                ..default()
+            }),
+        TargetCamera(player_camera),
+        MainMenu,
+        Name::new("MainMenu")
+    ));
+}
+
+fn enter_seed(
+    mut commands: Commands,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut seed_entry: ResMut<SeedEntry>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut menu_query: Query<&mut Text, With<MainMenu>>
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => seed_entry.text.push_str(characters),
+            Key::Backspace => { seed_entry.text.pop(); },
+            Key::Enter => {
+                // nothing typed leaves setup_rng to pick one
+                if seed_entry.text.trim().is_empty() {
+                    commands.remove_resource::<MazeSeed>();
+                    next_state.set(GameState::Initialize);
+                    continue;
+                }
+                match seed_entry.text.parse::<MazeSeed>() {
+                    Ok(seed) => {
+                        seed_entry.error = None;
+                        commands.insert_resource(seed);
+                        next_state.set(GameState::Initialize);
+                    },
+                    Err(error) => seed_entry.error = Some(error)
+                }
+            },
+            _ => {}
+        }
+    }
+
+    for mut text in &mut menu_query {
+        text.sections[SEED_TEXT_SECTION].value = seed_entry.text.clone();
+        text.sections[ERROR_TEXT_SECTION].value = seed_entry.error.as_ref().map(|error| format!("\n{}", error)).unwrap_or_default();
+    }
+}
+
+fn hide_main_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<MainMenu>>
+) {
+    for menu in &menu_query {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn new_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<SeedEntry>();
+        world.init_resource::<NextState<GameState>>();
+        world.init_resource::<Events<KeyboardInput>>();
+        world
+    }
+
+    fn press(world: &mut World, logical_key: Key) {
+        world.send_event(KeyboardInput { key_code: KeyCode::Unidentified(bevy::input::keyboard::NativeKeyCode::Unidentified), logical_key, state: ButtonState::Pressed, window: Entity::PLACEHOLDER });
+    }
+
+    fn type_seed(world: &mut World, text: &str) {
+        for character in text.chars() {
+            press(world, Key::Character(character.to_string().into()));
+        }
+        press(world, Key::Enter);
+        world.run_system_once(enter_seed);
+    }
+
+    fn is_starting(world: &World) -> bool {
+        matches!(world.resource::<NextState<GameState>>(), NextState::Pending(GameState::Initialize))
+    }
+
+    #[test]
+    fn starts_with_the_typed_seed() {
+        let mut world = new_world();
+        type_seed(&mut world, "f3k9-qz1t");
+        assert_eq!(world.get_resource::<MazeSeed>(), Some(&MazeSeed::from_code("F3K9-QZ1T").unwrap()));
+        assert!(is_starting(&world));
+    }
+
+    #[test]
+    fn stays_in_the_menu_for_a_bad_seed() {
+        let mut world = new_world();
+        type_seed(&mut world, "F3K9!");
+        assert!(world.resource::<SeedEntry>().error.is_some());
+        assert!(world.get_resource::<MazeSeed>().is_none());
+        assert!(!is_starting(&world));
+    }
+
+    #[test]
+    fn an_empty_seed_is_picked_at_random() {
+        let mut world = new_world();
+        world.insert_resource(MazeSeed(7));
+        world.resource_mut::<SeedEntry>().text = String::from("7");
+        press(&mut world, Key::Backspace);
+        type_seed(&mut world, "");
+        assert!(world.get_resource::<MazeSeed>().is_none());
+        assert!(is_starting(&world));
+    }
+}
@@ -0,0 +1 @@
+pub mod main_menu;
\ No newline at end of file
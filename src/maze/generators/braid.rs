@@ -0,0 +1,83 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::maze_cell_edge::EdgeType;
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+use crate::position::Position;
+
+/**
+ * Braiding removes walls after generation so the maze has loops in it.
+ * Without it every maze is perfect, and running from the monster into a dead end is the end of the game.
+ *
+ * dead_end_fraction is the chance each dead end gets one of its walls opened,
+ * wall_fraction is the chance any other wall between two cells gets opened.
+ * Walls on a room boundary become doors rather than passages.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BraidSettings {
+    pub dead_end_fraction: f32,
+    pub wall_fraction: f32
+}
+
+impl Default for BraidSettings {
+    fn default() -> Self {
+        BraidSettings {
+            dead_end_fraction: 0.5,
+            wall_fraction: 0.05
+        }
+    }
+}
+
+impl BraidSettings {
+    pub fn braid(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        self.braid_dead_ends(layout, rng);
+        self.braid_walls(layout, rng);
+    }
+
+    fn braid_dead_ends(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        for position in layout.get_positions() {
+            // an earlier opening may already have joined this cell up
+            if !is_dead_end(layout, &position) || rng.gen_range(0. .. 1.) >= self.dead_end_fraction {
+                continue;
+            }
+
+            let walls = get_openable_walls(layout, &position);
+            if walls.is_empty() {
+                continue;
+            }
+            let new_position = walls[rng.gen_range(0..walls.len())];
+            layout.open_wall(&position, &new_position, rng);
+        }
+    }
+
+    fn braid_walls(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        for position in layout.get_positions() {
+            // only east and south so each wall is considered once
+            for direction in [MazeDirection::EAST, MazeDirection::SOUTH] {
+                let new_position = &position + direction.to_position_modifier();
+                let is_wall = layout.get_cell(&position).is_some_and(|cell| cell.get_edge_type(&direction) == Some(EdgeType::Wall));
+                if is_wall && layout.contains_position(&new_position) && rng.gen_range(0. .. 1.) < self.wall_fraction {
+                    layout.open_wall(&position, &new_position, rng);
+                }
+            }
+        }
+    }
+}
+
+fn is_dead_end(layout: &MazeLayout, position: &Position) -> bool {
+    layout.get_cell(position).is_some_and(|cell| cell.count_edges_of_type(EdgeType::Wall) == 3)
+}
+
+// walls that have a cell on the other side, the outer walls of the maze stay put
+fn get_openable_walls(layout: &MazeLayout, position: &Position) -> Vec<Position> {
+    let Some(cell) = layout.get_cell(position) else {
+        return vec![];
+    };
+    (0..4)
+        .map(MazeDirection::get_direction_from_index)
+        .filter(|direction| cell.get_edge_type(direction) == Some(EdgeType::Wall))
+        .map(|direction| position + direction.to_position_modifier())
+        .filter(|new_position| layout.contains_position(new_position))
+        .collect()
+}
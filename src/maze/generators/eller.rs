@@ -0,0 +1,73 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+
+use super::maze_generator::MazeGenerator;
+use super::passage_graph::PassageGraph;
+
+const HORIZONTAL_JOIN_CHANCE: f64 = 0.5;
+
+/**
+ * Eller's algorithm: builds the maze one row at a time, only remembering which set each cell of the current row
+ * belongs to.  Cells are randomly joined along the row, then every set drops at least one passage into the next row.
+ * The last row joins everything that is still separate.
+ */
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut graph = PassageGraph::new(layout.size_x, layout.size_y);
+        let (size_x, size_y) = graph.get_size();
+
+        let mut next_set = 0;
+        let mut row_sets: Vec<Option<usize>> = vec![None; size_x as usize];
+
+        for y in 0..size_y {
+            let is_last_row = y == size_y - 1;
+
+            for set in row_sets.iter_mut().filter(|set| set.is_none()) {
+                *set = Some(next_set);
+                next_set += 1;
+            }
+
+            for x in 0..size_x - 1 {
+                let current_set = row_sets[x as usize];
+                let next_cell_set = row_sets[x as usize + 1];
+                if current_set != next_cell_set && (is_last_row || rng.gen_bool(HORIZONTAL_JOIN_CHANCE)) {
+                    graph.open_passage(graph.get_index(x, y), MazeDirection::EAST);
+                    for set in row_sets.iter_mut().filter(|set| **set == next_cell_set) {
+                        *set = current_set;
+                    }
+                }
+            }
+
+            if is_last_row {
+                break;
+            }
+
+            let mut sets_in_row: Vec<usize> = vec![];
+            for set in row_sets.iter().flatten() {
+                if !sets_in_row.contains(set) {
+                    sets_in_row.push(*set);
+                }
+            }
+
+            let mut next_row_sets = vec![None; size_x as usize];
+            for set in sets_in_row {
+                let mut columns: Vec<i32> = (0..size_x).filter(|x| row_sets[*x as usize] == Some(set)).collect();
+                columns.shuffle(rng);
+                let drop_count = rng.gen_range(1..=columns.len());
+                for x in &columns[..drop_count] {
+                    graph.open_passage(graph.get_index(*x, y), MazeDirection::SOUTH);
+                    next_row_sets[*x as usize] = Some(set);
+                }
+            }
+            row_sets = next_row_sets;
+        }
+
+        let start = rng.gen_range(0..graph.get_cell_count());
+        graph.carve_into_layout(layout, start, rng);
+    }
+}
@@ -0,0 +1,121 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::maze_layout::MazeLayout;
+use crate::position::Position;
+
+use super::maze_generator::MazeGenerator;
+
+// Which active cell the growing tree continues from on each step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CellSelection {
+    // long winding corridors, the catlikecoding default
+    Newest,
+    Random,
+    Oldest,
+    // picks the newest cell with the given chance, otherwise a random one
+    Mixed(f32)
+}
+
+impl CellSelection {
+    fn select_index(&self, active_count: usize, rng: &mut impl Rng) -> usize {
+        match self {
+            CellSelection::Newest => active_count - 1,
+            CellSelection::Random => rng.gen_range(0..active_count),
+            CellSelection::Oldest => 0,
+            CellSelection::Mixed(newest_chance) => {
+                if rng.gen_range(0. .. 1.) < *newest_chance {
+                    active_count - 1
+                } else {
+                    rng.gen_range(0..active_count)
+                }
+            }
+        }
+    }
+}
+
+/**
+ * The growing tree algorithm from the catlikecoding tutorial.  Rooms grow as the tree does: stepping into a
+ * new cell either continues the room or starts a new one behind a door, and running into a cell of a room with
+ * the same settings opens up a passage instead of a wall.
+ */
+pub struct GrowingTree {
+    selection: CellSelection
+}
+
+impl GrowingTree {
+    pub fn new(selection: CellSelection) -> Self {
+        GrowingTree { selection }
+    }
+
+    fn do_first_generation_step(&self, layout: &mut MazeLayout, active_positions: &mut Vec<Position>, rng: &mut impl Rng) {
+        let position = random_position(layout.size_x, layout.size_y, rng);
+        active_positions.push(position);
+
+        let room_index = layout.create_room_and_return_index(usize::MAX, rng);
+
+        layout.add_cell(&position, room_index)
+    }
+
+    fn do_next_generation_step(&self, layout: &mut MazeLayout, active_positions: &mut Vec<Position>, rng: &mut impl Rng) {
+        if active_positions.is_empty() {
+            panic!("Tried to step with an empty vec of positions!")
+        }
+        let active_index = self.selection.select_index(active_positions.len(), rng);
+        let position = active_positions[active_index];
+
+        let current_cell = layout.get_cell(&position).unwrap();
+        if current_cell.is_edge_complete() {
+            active_positions.remove(active_index);
+            return;
+        }
+
+        let new_position = &position + current_cell.get_random_unused_direction_for_cell(rng).to_position_modifier();
+        if layout.contains_position(&new_position) {
+            match layout.get_cell(&new_position) {
+                Some(entered_cell) => {
+                    if layout.get_settings_index_for_cell(current_cell) == layout.get_settings_index_for_cell(entered_cell) {
+                        let room_index = current_cell.get_room_index();
+                        let new_room_index = entered_cell.get_room_index();
+                        expand_room(layout, position, new_position, room_index, new_room_index, rng);
+                    } else {
+                        layout.add_wall(&position, &new_position, rng);
+                    }
+                },
+                None => {
+                    active_positions.push(new_position);
+                    layout.carve_into_new_cell(&position, &new_position, rng);
+                }
+            }
+        } else {
+            layout.add_wall(&position, &new_position, rng);
+        }
+    }
+}
+
+impl MazeGenerator for GrowingTree {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut active_positions: Vec<Position> = vec![];
+
+        self.do_first_generation_step(layout, &mut active_positions, rng);
+
+        while !active_positions.is_empty() {
+            self.do_next_generation_step(layout, &mut active_positions, rng);
+        }
+    }
+}
+
+// Opens a passage into a cell of a room with the same settings, if it's a different room the two become one
+fn expand_room(layout: &mut MazeLayout, position: Position, new_position: Position, room_index: usize, new_room_index: usize, rng: &mut impl Rng) {
+    if room_index != new_room_index {
+        layout.merge_rooms(room_index, new_room_index);
+    }
+    layout.add_passage(&position, &new_position, rng);
+}
+
+fn random_position(max_x: i32, max_y: i32, rng: &mut impl Rng) -> Position {
+    let random_x = rng.gen_range(0..max_x);
+    let random_y = rng.gen_range(0..max_y);
+
+    Position::new_from_i32(random_x, random_y)
+}
@@ -0,0 +1,78 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+
+use super::maze_generator::MazeGenerator;
+use super::passage_graph::PassageGraph;
+
+/**
+ * Randomized Kruskal's: shuffle every wall in the grid and remove each one that separates two cells
+ * which are not yet connected, tracked with a union-find over the cells.
+ */
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut graph = PassageGraph::new(layout.size_x, layout.size_y);
+
+        // only east and south so each wall is listed once
+        let mut walls = vec![];
+        for index in 0..graph.get_cell_count() {
+            for direction in [MazeDirection::EAST, MazeDirection::SOUTH] {
+                if graph.get_neighbour(index, direction).is_some() {
+                    walls.push((index, direction));
+                }
+            }
+        }
+        walls.shuffle(rng);
+
+        let mut sets = DisjointSets::new(graph.get_cell_count());
+        for (index, direction) in walls {
+            let neighbour = graph.get_neighbour(index, direction).unwrap();
+            if sets.union(index, neighbour) {
+                graph.open_passage(index, direction);
+            }
+        }
+
+        let start = rng.gen_range(0..graph.get_cell_count());
+        graph.carve_into_layout(layout, start, rng);
+    }
+}
+
+struct DisjointSets {
+    parents: Vec<usize>
+}
+
+impl DisjointSets {
+    fn new(size: usize) -> Self {
+        DisjointSets { parents: (0..size).collect() }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // flatten the path so later finds are quick
+        let mut current = index;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    // returns false if the two were already in the same set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return false;
+        }
+        self.parents[root_b] = root_a;
+        true
+    }
+}
@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::maze_layout::MazeLayout;
+
+use super::eller::Eller;
+use super::growing_tree::{CellSelection, GrowingTree};
+use super::kruskal::Kruskal;
+use super::prim::Prim;
+use super::recursive_division::RecursiveDivision;
+use super::wilson::Wilson;
+
+/**
+ * A MazeGenerator fills an empty MazeLayout with cells, rooms and edges.
+ * Whatever algorithm is used, the output is the same MazeCell/EdgeType data, so rendering,
+ * collisions and rooms do not care which one built the maze.
+ */
+pub trait MazeGenerator {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng);
+}
+
+// Which generator to use, read from the MazeConfig when entering GameState::Initialize
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MazeAlgorithm {
+    GrowingTree(CellSelection),
+    Prim,
+    Kruskal,
+    Wilson,
+    Eller,
+    RecursiveDivision
+}
+
+impl Default for MazeAlgorithm {
+    fn default() -> Self {
+        MazeAlgorithm::GrowingTree(CellSelection::Newest)
+    }
+}
+
+impl MazeGenerator for MazeAlgorithm {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        match self {
+            MazeAlgorithm::GrowingTree(selection) => GrowingTree::new(*selection).generate(layout, rng),
+            MazeAlgorithm::Prim => Prim.generate(layout, rng),
+            MazeAlgorithm::Kruskal => Kruskal.generate(layout, rng),
+            MazeAlgorithm::Wilson => Wilson.generate(layout, rng),
+            MazeAlgorithm::Eller => Eller.generate(layout, rng),
+            MazeAlgorithm::RecursiveDivision => RecursiveDivision.generate(layout, rng),
+        }
+    }
+}
+
+impl FromStr for MazeAlgorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "growing-tree" | "growing-tree-newest" => Ok(MazeAlgorithm::GrowingTree(CellSelection::Newest)),
+            "growing-tree-random" => Ok(MazeAlgorithm::GrowingTree(CellSelection::Random)),
+            "growing-tree-oldest" => Ok(MazeAlgorithm::GrowingTree(CellSelection::Oldest)),
+            "growing-tree-mixed" => Ok(MazeAlgorithm::GrowingTree(CellSelection::Mixed(0.5))),
+            "prim" => Ok(MazeAlgorithm::Prim),
+            "kruskal" => Ok(MazeAlgorithm::Kruskal),
+            "wilson" => Ok(MazeAlgorithm::Wilson),
+            "eller" => Ok(MazeAlgorithm::Eller),
+            "recursive-division" => Ok(MazeAlgorithm::RecursiveDivision),
+            _ => Err(format!("Unknown maze algorithm: {}", name))
+        }
+    }
+}
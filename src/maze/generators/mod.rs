@@ -0,0 +1,9 @@
+pub mod maze_generator;
+pub mod passage_graph;
+pub mod growing_tree;
+pub mod prim;
+pub mod kruskal;
+pub mod wilson;
+pub mod eller;
+pub mod recursive_division;
+pub mod braid;
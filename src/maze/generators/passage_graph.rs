@@ -0,0 +1,116 @@
+use rand::Rng;
+
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+use crate::position::Position;
+
+/**
+ * Most of the classic maze algorithms only care about which neighbouring cells are connected.
+ * They build one of these, and carve_into_layout then turns the connections into cells, rooms, doors and walls
+ * the same way the growing tree does, so every algorithm ends up with the same kind of MazeLayout.
+ */
+pub struct PassageGraph {
+    size_x: i32,
+    size_y: i32,
+    // indexed by cell, then by MazeDirection
+    passages: Vec<[bool; 4]>
+}
+
+impl PassageGraph {
+    pub fn new(size_x: i32, size_y: i32) -> Self {
+        PassageGraph {
+            size_x,
+            size_y,
+            passages: vec![[false; 4]; (size_x * size_y).max(0) as usize]
+        }
+    }
+
+    // every neighbouring cell connected, used by algorithms that add walls instead of carving passages
+    pub fn new_fully_open(size_x: i32, size_y: i32) -> Self {
+        let mut graph = PassageGraph::new(size_x, size_y);
+        for index in 0..graph.get_cell_count() {
+            for (direction, _) in graph.get_neighbours(index) {
+                graph.passages[index][direction as usize] = true;
+            }
+        }
+        graph
+    }
+
+    pub fn get_size(&self) -> (i32, i32) {
+        (self.size_x, self.size_y)
+    }
+
+    pub fn get_cell_count(&self) -> usize {
+        self.passages.len()
+    }
+
+    pub fn get_index(&self, x: i32, y: i32) -> usize {
+        (y * self.size_x + x) as usize
+    }
+
+    pub fn get_coordinates(&self, index: usize) -> (i32, i32) {
+        (index as i32 % self.size_x, index as i32 / self.size_x)
+    }
+
+    pub fn get_position(&self, index: usize) -> Position {
+        let (x, y) = self.get_coordinates(index);
+        Position::new_from_i32(x, y)
+    }
+
+    pub fn get_neighbour(&self, index: usize, direction: MazeDirection) -> Option<usize> {
+        let (x, y) = self.get_coordinates(index);
+        let modifier = direction.to_position_modifier();
+        let (new_x, new_y) = (x + modifier.x as i32, y + modifier.y as i32);
+        if new_x < 0 || new_y < 0 || new_x >= self.size_x || new_y >= self.size_y {
+            return None;
+        }
+        Some(self.get_index(new_x, new_y))
+    }
+
+    pub fn get_neighbours(&self, index: usize) -> Vec<(MazeDirection, usize)> {
+        (0..4)
+            .map(MazeDirection::get_direction_from_index)
+            .filter_map(|direction| self.get_neighbour(index, direction).map(|neighbour| (direction, neighbour)))
+            .collect()
+    }
+
+    pub fn set_passage(&mut self, index: usize, direction: MazeDirection, is_open: bool) {
+        let neighbour = self.get_neighbour(index, direction).expect("set_passage: no cell in that direction");
+        self.passages[index][direction as usize] = is_open;
+        self.passages[neighbour][direction.get_opposite_direction() as usize] = is_open;
+    }
+
+    pub fn open_passage(&mut self, index: usize, direction: MazeDirection) {
+        self.set_passage(index, direction, true);
+    }
+
+    pub fn close_passage(&mut self, index: usize, direction: MazeDirection) {
+        self.set_passage(index, direction, false);
+    }
+
+    pub fn is_open(&self, index: usize, direction: MazeDirection) -> bool {
+        self.passages[index][direction as usize]
+    }
+
+    // Walks the passages outward from the start cell, growing rooms and placing doors as it goes.
+    // Anything left unconnected afterwards is closed off by the layout.
+    pub fn carve_into_layout(&self, layout: &mut MazeLayout, start: usize, rng: &mut impl Rng) {
+        let room_index = layout.create_room_and_return_index(usize::MAX, rng);
+        layout.add_cell(&self.get_position(start), room_index);
+
+        let mut active_cells = vec![start];
+        while let Some(index) = active_cells.pop() {
+            let position = self.get_position(index);
+            for (direction, neighbour) in self.get_neighbours(index) {
+                let new_position = self.get_position(neighbour);
+                if !self.is_open(index, direction) || layout.get_cell(&new_position).is_some() {
+                    continue;
+                }
+                layout.carve_into_new_cell(&position, &new_position, rng);
+                active_cells.push(neighbour);
+            }
+        }
+
+        layout.close_remaining_edges(rng);
+    }
+}
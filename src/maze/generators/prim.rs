@@ -0,0 +1,41 @@
+use rand::Rng;
+
+use crate::maze::maze_layout::MazeLayout;
+
+use super::maze_generator::MazeGenerator;
+use super::passage_graph::PassageGraph;
+
+/**
+ * Randomized Prim's: keep a frontier of walls between the maze and the cells outside it,
+ * and knock down a random one each step.  Gives lots of short dead ends.
+ */
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut graph = PassageGraph::new(layout.size_x, layout.size_y);
+        let start = rng.gen_range(0..graph.get_cell_count());
+
+        let mut in_maze = vec![false; graph.get_cell_count()];
+        in_maze[start] = true;
+        let mut frontier: Vec<_> = graph.get_neighbours(start).into_iter().map(|(direction, _)| (start, direction)).collect();
+
+        while !frontier.is_empty() {
+            let (index, direction) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            let neighbour = graph.get_neighbour(index, direction).expect("frontier wall leads out of the maze");
+            if in_maze[neighbour] {
+                continue;
+            }
+
+            graph.open_passage(index, direction);
+            in_maze[neighbour] = true;
+            for (next_direction, next_neighbour) in graph.get_neighbours(neighbour) {
+                if !in_maze[next_neighbour] {
+                    frontier.push((neighbour, next_direction));
+                }
+            }
+        }
+
+        graph.carve_into_layout(layout, start, rng);
+    }
+}
@@ -0,0 +1,59 @@
+use rand::Rng;
+
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+
+use super::maze_generator::MazeGenerator;
+use super::passage_graph::PassageGraph;
+
+/**
+ * Recursive division: start with one open field, split it with a wall that has a single gap in it,
+ * then keep splitting each side until the chambers are a single cell wide.  Gives long straight walls.
+ */
+pub struct RecursiveDivision;
+
+impl MazeGenerator for RecursiveDivision {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut graph = PassageGraph::new_fully_open(layout.size_x, layout.size_y);
+        let (size_x, size_y) = graph.get_size();
+
+        divide(&mut graph, 0, 0, size_x, size_y, rng);
+
+        let start = rng.gen_range(0..graph.get_cell_count());
+        graph.carve_into_layout(layout, start, rng);
+    }
+}
+
+fn divide(graph: &mut PassageGraph, x: i32, y: i32, width: i32, height: i32, rng: &mut impl Rng) {
+    if width < 2 || height < 2 {
+        return;
+    }
+
+    let split_horizontally = if width < height {
+        true
+    } else if height < width {
+        false
+    } else {
+        rng.gen_bool(0.5)
+    };
+
+    if split_horizontally {
+        // the wall runs along the south side of wall_y
+        let wall_y = y + rng.gen_range(0..height - 1);
+        let gap_x = x + rng.gen_range(0..width);
+        for wall_x in (x..x + width).filter(|wall_x| *wall_x != gap_x) {
+            graph.close_passage(graph.get_index(wall_x, wall_y), MazeDirection::SOUTH);
+        }
+        divide(graph, x, y, width, wall_y - y + 1, rng);
+        divide(graph, x, wall_y + 1, width, y + height - wall_y - 1, rng);
+    } else {
+        // the wall runs along the east side of wall_x
+        let wall_x = x + rng.gen_range(0..width - 1);
+        let gap_y = y + rng.gen_range(0..height);
+        for wall_y in (y..y + height).filter(|wall_y| *wall_y != gap_y) {
+            graph.close_passage(graph.get_index(wall_x, wall_y), MazeDirection::EAST);
+        }
+        divide(graph, x, y, wall_x - x + 1, height, rng);
+        divide(graph, wall_x + 1, y, x + width - wall_x - 1, height, rng);
+    }
+}
@@ -0,0 +1,55 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::maze::maze_direction::MazeDirection;
+use crate::maze::maze_layout::MazeLayout;
+
+use super::maze_generator::MazeGenerator;
+use super::passage_graph::PassageGraph;
+
+/**
+ * Wilson's algorithm: from each cell not yet in the maze, random walk until the maze is hit, erasing any loops
+ * along the way, then add the walked path.  Produces an unbiased sample of all possible perfect mazes.
+ */
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn generate(&self, layout: &mut MazeLayout, rng: &mut impl Rng) {
+        let mut graph = PassageGraph::new(layout.size_x, layout.size_y);
+        let cell_count = graph.get_cell_count();
+
+        let start = rng.gen_range(0..cell_count);
+        let mut in_maze = vec![false; cell_count];
+        in_maze[start] = true;
+
+        let mut walk_starts: Vec<usize> = (0..cell_count).collect();
+        walk_starts.shuffle(rng);
+
+        // the direction last taken out of each cell, overwriting it is what erases loops
+        let mut walk_directions: Vec<Option<MazeDirection>> = vec![None; cell_count];
+
+        for walk_start in walk_starts {
+            if in_maze[walk_start] {
+                continue;
+            }
+
+            let mut current = walk_start;
+            while !in_maze[current] {
+                let neighbours = graph.get_neighbours(current);
+                let (direction, neighbour) = neighbours[rng.gen_range(0..neighbours.len())];
+                walk_directions[current] = Some(direction);
+                current = neighbour;
+            }
+
+            let mut current = walk_start;
+            while !in_maze[current] {
+                let direction = walk_directions[current].expect("walked cell has no direction");
+                graph.open_passage(current, direction);
+                in_maze[current] = true;
+                current = graph.get_neighbour(current, direction).unwrap();
+            }
+        }
+
+        graph.carve_into_layout(layout, start, rng);
+    }
+}
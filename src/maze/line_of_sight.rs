@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::position::Position;
+use super::{maze_direction::MazeDirection, maze_layout::MazeLayout, room_links::RoomLinks};
+
+/**
+ * Walks the cells a straight line from one point to another passes through, and checks every edge it crosses.
+ * Points are in maze units (a world position divided by the maze scale), so cell centers sit on whole numbers.
+ * Walls and closed doors block the line, passages and open doors don't.
+ */
+pub fn has_line_of_sight(layout: &MazeLayout, room_links: &RoomLinks, from: Vec2, to: Vec2) -> bool {
+    let mut cell = Position::new(from.x.round(), from.y.round());
+    let end = Position::new(to.x.round(), to.y.round());
+    let delta = to - from;
+
+    // how far along the line (0 at from, 1 at to) the next cell boundary is on each axis, and how far between boundaries
+    let step = Vec2::new(delta.x.signum(), delta.y.signum());
+    let t_delta = Vec2::new(1.0 / delta.x.abs(), 1.0 / delta.y.abs());
+    let mut t_max = Vec2::new(
+        if delta.x == 0.0 { f32::INFINITY } else { (cell.x + 0.5 * step.x - from.x) / delta.x },
+        if delta.y == 0.0 { f32::INFINITY } else { (cell.y + 0.5 * step.y - from.y) / delta.y }
+    );
+
+    while cell != end && t_max.min_element() <= 1.0 {
+        let next = if t_max.x < t_max.y {
+            t_max.x += t_delta.x;
+            Position::new(cell.x + step.x, cell.y)
+        } else {
+            t_max.y += t_delta.y;
+            Position::new(cell.x, cell.y + step.y)
+        };
+        if !can_see_between(layout, room_links, cell, next) {
+            return false;
+        }
+        cell = next;
+    }
+    true
+}
+
+fn can_see_between(layout: &MazeLayout, room_links: &RoomLinks, position: Position, next: Position) -> bool {
+    let Some(cell) = layout.get_cell(&position) else { return false };
+    if layout.get_cell(&next).is_none() {
+        return false;
+    }
+    let direction = MazeDirection::get_direction_position_from_positions(&position, &next);
+    match cell.get_edges().find(|(edge_direction, _)| **edge_direction == direction) {
+        Some((_, None)) => true,
+        Some((_, Some(edge))) => edge.is_passable(room_links, &position),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::maze_import::test_layouts::{import, HAIRPIN};
+
+    use super::*;
+
+    // a door between the end of room a and room b
+    const ONE_DOOR: &str = "
++-+-+-+
+|S aDb|
++ +-+-+
+|E a a|
++-+-+-+
+";
+
+    // a corner of wall sticking into a small room, between (0, 0) and (0, 1)
+    const CORNER: &str = "
++-+-+
+|S a|
++-+ +
+|E a|
++-+-+
+";
+
+    #[test]
+    fn walls_block_the_view() {
+        let layout = import(HAIRPIN);
+        assert!(has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(0., 0.), Vec2::new(2., 0.)));
+        assert!(has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(2., 0.), Vec2::new(2., 1.)));
+        assert!(!has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(0., 0.), Vec2::new(0., 1.)));
+        assert!(!has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(0., 1.), Vec2::new(2., 0.)));
+    }
+
+    #[test]
+    fn closed_doors_block_the_view() {
+        let layout = import(ONE_DOOR);
+        assert!(!has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(0., 0.), Vec2::new(2., 0.)));
+        assert!(!has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(2., 0.), Vec2::new(1., 0.)));
+    }
+
+    #[test]
+    fn open_doors_can_be_seen_through() {
+        let layout = import(ONE_DOOR);
+        let mut room_links = layout.get_room_links().clone();
+        room_links.open_link_between(&Position::new_from_i32(1, 0), &Position::new_from_i32(2, 0));
+        assert!(has_line_of_sight(&layout, &room_links, Vec2::new(0., 0.), Vec2::new(2., 0.)));
+        assert!(has_line_of_sight(&layout, &room_links, Vec2::new(2., 0.), Vec2::new(1., 0.)));
+        // the other walls round the door still block
+        assert!(!has_line_of_sight(&layout, &room_links, Vec2::new(2., 0.), Vec2::new(2., 1.)));
+    }
+
+    #[test]
+    fn diagonals_pass_corners_on_the_open_side_only() {
+        let layout = import(CORNER);
+        // starts a little higher up the map, so it crosses into (1, 0) before (0, 1) and goes round the end of the wall
+        assert!(has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(0., -0.2), Vec2::new(1., 1.)));
+        // starts a little further left, so it clips (0, 1) through the wall first
+        assert!(!has_line_of_sight(&layout, layout.get_room_links(), Vec2::new(-0.2, 0.), Vec2::new(1., 1.)));
+    }
+}
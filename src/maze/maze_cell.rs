@@ -1,10 +1,10 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::f32::consts::*;
 
 use bevy::{prelude::*, render::view::RenderLayers};
 use rand::Rng;
 
-use crate::{consts, player::{player::LogicalPlayer, player_events::PlayerCellChangeEvent}, position::{MazePosition, Position}, random::Random};
+use crate::{player::{player::LogicalPlayer, player_events::PlayerCellChangeEvent}, position::{MazePosition, Position}};
 
 use super::{maze_cell_edge::{EdgeType, MazeCellEdge}, maze_direction::MazeDirection, maze_door::MazeDoor, maze_room::RoomAssets};
 
@@ -13,7 +13,8 @@ pub struct MazeCell {
     position: Position,
     render: bool,
     defined_edges: Vec<MazeDirection>,
-    edges: HashMap<MazeDirection, Option<MazeCellEdge>>,
+    // ordered so anything walking the edges (like rendering) always does so in the same order
+    edges: BTreeMap<MazeDirection, Option<MazeCellEdge>>,
     entity: Option<Entity>,
     room_index: usize
 }
@@ -24,7 +25,7 @@ impl MazeCell {
             position: Position::new( x, y ),
             render: false,
             defined_edges: vec![],
-            edges: HashMap::new(),
+            edges: BTreeMap::new(),
             entity: None,
             room_index
         }
@@ -64,15 +65,29 @@ impl MazeCell {
     //     }
     // }
 
-    pub fn add_edge(&mut self, maze_direction: &MazeDirection, edge_type: Option<EdgeType>, rand: &mut ResMut<Random>) {
+    pub fn add_edge(&mut self, maze_direction: &MazeDirection, edge_type: Option<EdgeType>, wall_light_probability: f32, rng: &mut impl Rng) {
         if self.has_edge(maze_direction) {
             panic!("Pushed same edge twice, stopping");
         }
 
+        self.insert_edge(maze_direction, edge_type, wall_light_probability, rng);
+        self.defined_edges.push(*maze_direction);
+    }
+
+    // used by post-passes like braiding that change an edge after generation
+    pub fn replace_edge(&mut self, maze_direction: &MazeDirection, edge_type: Option<EdgeType>, wall_light_probability: f32, rng: &mut impl Rng) {
+        if !self.has_edge(maze_direction) {
+            panic!("Tried to replace an edge that was never added");
+        }
+
+        self.insert_edge(maze_direction, edge_type, wall_light_probability, rng);
+    }
+
+    fn insert_edge(&mut self, maze_direction: &MazeDirection, edge_type: Option<EdgeType>, wall_light_probability: f32, rng: &mut impl Rng) {
         match edge_type {
             Some(edge_type) => {
                 let mut new_edge = MazeCellEdge::new(maze_direction, edge_type);
-                new_edge.generate_furniture(rand);
+                new_edge.generate_furniture(wall_light_probability, rng);
                 let new_edge_option = Some(new_edge);
                 self.edges.insert(*maze_direction, new_edge_option);
             },
@@ -80,10 +95,34 @@ impl MazeCell {
                 self.edges.insert(*maze_direction, None);
             },
         }
+    }
 
+    // for loading a saved maze, where the edge (None for a passage) is already fully built
+    pub fn add_saved_edge(&mut self, maze_direction: &MazeDirection, edge: Option<MazeCellEdge>) {
+        if self.has_edge(maze_direction) {
+            panic!("Pushed same edge twice, stopping");
+        }
+
+        self.edges.insert(*maze_direction, edge);
         self.defined_edges.push(*maze_direction);
     }
 
+    pub fn get_edges(&self) -> impl Iterator<Item = (&MazeDirection, &Option<MazeCellEdge>)> {
+        self.edges.iter()
+    }
+
+    // None for a passage, or for an edge that has not been defined yet
+    pub fn get_edge_type(&self, maze_direction: &MazeDirection) -> Option<EdgeType> {
+        match self.edges.get(maze_direction) {
+            Some(Some(edge)) => Some(edge.get_edge_type()),
+            _ => None
+        }
+    }
+
+    pub fn count_edges_of_type(&self, edge_type: EdgeType) -> usize {
+        self.edges.values().flatten().filter(|edge| edge.get_edge_type() == edge_type).count()
+    }
+
     pub fn has_edge(&self, maze_direction: &MazeDirection) -> bool {
         self.defined_edges.contains(maze_direction)
     }
@@ -92,8 +131,8 @@ impl MazeCell {
         self.defined_edges.len() == 4
     }
 
-    pub fn get_random_unused_direction_for_cell(&self, rand: &mut ResMut<Random>) -> MazeDirection {
-        let mut skips: usize = rand.gen_range(0..4-self.defined_edges.len());
+    pub fn get_random_unused_direction_for_cell(&self, rng: &mut impl Rng) -> MazeDirection {
+        let mut skips: usize = rng.gen_range(0..4-self.defined_edges.len());
         for i in 0..4 {
             let new_direction = &MazeDirection::get_direction_from_index(i);
             if !self.has_edge(new_direction) {
@@ -109,19 +148,19 @@ impl MazeCell {
         panic!("Ran out of possible edges before ran out of skips");
     }
 
-    pub fn render_cell(&mut self, commands: &mut Commands<'_, '_>, meshes: &mut ResMut<'_, Assets<Mesh>>, floor_material: Handle<StandardMaterial>, room_assets: RoomAssets, floors: Entity) {
-        let translation = self.get_position().to_vec3_by_scale(consts::MAZE_SCALE);
+    pub fn render_cell(&mut self, commands: &mut Commands<'_, '_>, meshes: &mut ResMut<'_, Assets<Mesh>>, floor_material: Handle<StandardMaterial>, room_assets: RoomAssets, floors: Entity, maze_scale: f32) {
+        let translation = self.get_position().to_vec3_by_scale(maze_scale);
         if self.is_render() {
-            self.render_floor(commands, meshes, floor_material, translation, floors);
-            self.render_ceiling(commands, &room_assets);
-            self.render_walls(commands, &room_assets);
+            self.render_floor(commands, meshes, floor_material, translation, floors, maze_scale);
+            self.render_ceiling(commands, &room_assets, maze_scale);
+            self.render_walls(commands, &room_assets, maze_scale);
         }
     }
 
-    fn render_floor(&mut self, commands: &mut Commands<'_, '_>, meshes: &mut ResMut<'_, Assets<Mesh>>, floor_material: Handle<StandardMaterial>, translation: Vec3, floors: Entity) {
+    fn render_floor(&mut self, commands: &mut Commands<'_, '_>, meshes: &mut ResMut<'_, Assets<Mesh>>, floor_material: Handle<StandardMaterial>, translation: Vec3, floors: Entity, maze_scale: f32) {
         let floor = commands.spawn( (
             PbrBundle {
-                mesh: meshes.add(Rectangle::new(consts::MAZE_SCALE, consts::MAZE_SCALE)),
+                mesh: meshes.add(Rectangle::new(maze_scale, maze_scale)),
                 material: floor_material,
                 transform: Transform { translation, rotation: Quat::from_rotation_x(-FRAC_PI_2), ..default() },
                 ..default()
@@ -133,9 +172,9 @@ impl MazeCell {
         commands.entity(floors).push_children(&[floor]);
     }
 
-    fn render_ceiling(&mut self, commands: &mut Commands, room_assets: &RoomAssets) {
+    fn render_ceiling(&mut self, commands: &mut Commands, room_assets: &RoomAssets, maze_scale: f32) {
         // TODO: Make this only render for the FPS camera and not the top down camera
-        let half_cell = consts::MAZE_SCALE / 2.;
+        let half_cell = maze_scale / 2.;
         let transform = Transform::from_xyz(-half_cell, half_cell, 6.0)
             .with_rotation(Quat::from_euler(EulerRot::XYZ, FRAC_PI_2, 0.0, 0.0 ))
             .with_scale(Vec3::splat(2.0));
@@ -155,13 +194,14 @@ impl MazeCell {
     fn render_walls(
         &mut self,
         commands: &mut Commands<'_, '_>,
-        room_assets: &RoomAssets) {
+        room_assets: &RoomAssets,
+        maze_scale: f32) {
     
         for (_maze_direction, edge) in &mut self.edges {
             match edge {
                 Some(edge) => {
                     if edge.get_edge_type() == EdgeType::Doorway || edge.get_edge_type() == EdgeType::Wall {
-                        let new_edge = edge.create_edge_entity(commands, room_assets);
+                        let new_edge = edge.create_edge_entity(commands, room_assets, maze_scale);
                         commands
                             .entity(self.entity.expect("somehow adding edge entity to non-existant floor"))
                             .push_children(&[new_edge.expect("somehow adding edge that isn't an edge")]);
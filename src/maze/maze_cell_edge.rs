@@ -1,11 +1,12 @@
 use bevy::{ecs::observer::TriggerTargets, prelude::*};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use super::{maze_direction::MazeDirection, maze_door::MazeDoor, maze_room::RoomAssets, paintings::Painting};
-use crate::{consts, physics::collider::Collider, random::Random};
+use super::{maze_direction::MazeDirection, maze_door::{MazeDoor, MazeDoorway}, maze_room::RoomAssets, paintings::Painting, room_links::RoomLinks};
+use crate::{consts, physics::collider::Collider, position::Position};
 
 
-#[derive(Default, Copy, Clone, PartialEq)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum EdgeType {
     #[default]
     Wall,
@@ -22,6 +23,9 @@ pub struct MazeCellEdge {
     door: Option<Entity>
 }
 
+// how long the wall model is before it's scaled up
+const WALL_LENGTH: f32 = 2.5;
+
 #[derive(Component, Debug, Deref, DerefMut)]
 pub struct WallPosition(pub MazeDirection);
 
@@ -30,6 +34,19 @@ impl MazeCellEdge {
         MazeCellEdge { maze_direction: *maze_direction, edge_type, painting: None, wall_furniture: vec![], door: None }
     }
 
+    // rebuilds an edge exactly as it was saved, instead of rolling new furniture
+    pub fn from_saved(maze_direction: &MazeDirection, edge_type: EdgeType, painting: Option<Painting>, wall_furniture: Vec<String>) -> MazeCellEdge {
+        MazeCellEdge { maze_direction: *maze_direction, edge_type, painting, wall_furniture, door: None }
+    }
+
+    pub fn get_painting(&self) -> Option<&Painting> {
+        self.painting.as_ref()
+    }
+
+    pub fn get_wall_furniture(&self) -> &Vec<String> {
+        &self.wall_furniture
+    }
+
     pub fn get_edge_type(&self) -> EdgeType {
         self.edge_type
     }
@@ -38,37 +55,40 @@ impl MazeCellEdge {
         self.maze_direction
     }
 
-    pub fn generate_furniture(&mut self, rand: &mut ResMut<Random>) {
+    pub fn generate_furniture(&mut self, wall_light_probability: f32, rng: &mut impl Rng) {
         if self.get_edge_type() == EdgeType::Wall {
-            let light_chance = rand.gen_range(0.0..1.);
-            if light_chance < consts::WALL_LIGHT_PROBABILITY {
+            let light_chance = rng.gen_range(0.0..1.);
+            if light_chance < wall_light_probability {
                 // Add a wall light
                 self.wall_furniture.push(String::from("wall_light"));
             }    
         }
     }
 
-    // Ideally we would have some way for a cell to say whether it is possible to move from one cell to another.
-    // ...or maybe this could be about room links?
-    pub fn is_passable(&self) -> bool {
-        if (self.edge_type == EdgeType::Doorway || self.edge_type == EdgeType::InverseDoorway) // && self.is_open {
-        {
-            return true;
-        }
-        false
+    // whether there's a door here at all, open or not
+    pub fn is_doorway(&self) -> bool {
+        self.edge_type == EdgeType::Doorway || self.edge_type == EdgeType::InverseDoorway
+    }
+
+    // Whether it is possible to move or see from one cell to the other through this edge right now.  Walls never are,
+    // doors are while the RoomLinks have them open.  position is the cell this edge belongs to
+    pub fn is_passable(&self, room_links: &RoomLinks, position: &Position) -> bool {
+        self.is_doorway() && room_links.is_open_between(position, &(position + self.maze_direction.to_position_modifier()))
     }
 
     pub fn create_edge_entity(
         &mut self,
         commands: &mut Commands<'_, '_>,
         room_assets: &RoomAssets,
+        maze_scale: f32
     ) -> Option<Entity> {
         if self.get_edge_type() == EdgeType::Wall {
-            let translation: Vec3 = self.get_maze_direction().get_wall_position_for_cell();
+            let translation: Vec3 = self.get_maze_direction().get_wall_position_for_cell(maze_scale);
             let rotation = self.get_maze_direction().get_direction_quat();
+            // scaled to span the cell, like the doorways
             let transform = Transform::from_xyz(translation.x, translation.y, translation.z)
                 .with_rotation(rotation)
-                .with_scale(Vec3::splat(2.));
+                .with_scale(Vec3::splat(maze_scale / WALL_LENGTH));
     
             let wall = commands.spawn( (
                 SceneBundle {
@@ -111,11 +131,12 @@ impl MazeCellEdge {
 
             Some(wall)
         } else if self.get_edge_type() == EdgeType::Doorway {
-            let translation: Vec3 = self.get_maze_direction().get_door_position_for_cell();
+            let translation: Vec3 = self.get_maze_direction().get_door_position_for_cell(maze_scale);
             let rotation = self.get_maze_direction().get_direction_quat();
+            // scaled to span the cell, so the door and its colliders line up whatever the maze scale
             let transform = Transform::from_xyz(translation.x, translation.y, translation.z)
                 .with_rotation(rotation)
-                .with_scale(Vec3::new(2.0, 2.0, 2.0));
+                .with_scale(Vec3::splat(MazeDoor::get_doorway_scale(maze_scale)));
 
             let doorway = commands.spawn((
                 SceneBundle {
@@ -124,6 +145,7 @@ impl MazeCellEdge {
                     ..default()
                 },
                 Collider,
+                MazeDoorway,
                 Name::new(format!("Door {:#?}", self.get_maze_direction()))
             )).id();
 
@@ -0,0 +1,119 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::generators::braid::BraidSettings;
+use super::generators::maze_generator::MazeAlgorithm;
+
+pub const DEFAULT_CONFIG_PATH: &str = "maze_config.ron";
+
+/**
+ * Everything that decides the size and shape of a maze, and how fast the game ticks.  Loaded once at startup from a RON file
+ * (maze_config.ron unless --config says otherwise), then any command line flags are applied on top.
+ * Anything missing from the file keeps its default.
+ *
+ * Command line flags:
+ * --config <path>, --maze-x <i32>, --maze-y <i32>, --maze-scale <f32>, --door-probability <f32>,
+ * --wall-light-probability <f32>, --painting-probability <f32>, --algorithm <name>,
+ * --braid-dead-ends <f32>, --braid-walls <f32>, --tick-rate <f64>
+ */
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MazeConfig {
+    pub maze_x: i32,
+    pub maze_y: i32,
+    pub maze_scale: f32,
+    // all probabilities range from 0-1
+    pub door_probability: f32,
+    pub wall_light_probability: f32,
+    pub painting_probability: f32,
+    pub algorithm: MazeAlgorithm,
+    pub braid: BraidSettings,
+    // how many times a second movement, physics and doors are stepped, whatever the frame rate
+    pub tick_rate: f64
+}
+
+impl Default for MazeConfig {
+    fn default() -> Self {
+        MazeConfig {
+            maze_x: 5,
+            maze_y: 5,
+            maze_scale: 5.,
+            door_probability: 0.3,
+            wall_light_probability: 0.25,
+            painting_probability: 0.25,
+            algorithm: MazeAlgorithm::default(),
+            braid: BraidSettings::default(),
+            tick_rate: 64.
+        }
+    }
+}
+
+impl MazeConfig {
+    pub fn load_from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let args: Vec<String> = args.collect();
+
+        let config_path = match args.iter().position(|arg| arg == "--config") {
+            Some(index) => Some(args.get(index + 1).ok_or("--config needs a path")?.clone()),
+            None => None
+        };
+
+        let mut config = match config_path {
+            Some(path) => MazeConfig::load_from_file(&path)?,
+            // the default file is optional
+            None => MazeConfig::load_from_file(DEFAULT_CONFIG_PATH).unwrap_or_default()
+        };
+
+        config.apply_args(&args)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Could not read maze config {}: {}", path, error))?;
+        ron::from_str(&contents).map_err(|error| format!("Could not parse maze config {}: {}", path, error))
+    }
+
+    // Applies the flags it knows about and leaves anything else for whoever else reads the command line
+    pub fn apply_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut index = 0;
+        while index < args.len() {
+            let flag = args[index].as_str();
+            let value = args.get(index + 1).map(|value| value.as_str());
+            let consumed = match flag {
+                "--maze-x" => { self.maze_x = parse_flag(flag, value)?; true },
+                "--maze-y" => { self.maze_y = parse_flag(flag, value)?; true },
+                "--maze-scale" => { self.maze_scale = parse_flag(flag, value)?; true },
+                "--door-probability" => { self.door_probability = parse_flag(flag, value)?; true },
+                "--wall-light-probability" => { self.wall_light_probability = parse_flag(flag, value)?; true },
+                "--painting-probability" => { self.painting_probability = parse_flag(flag, value)?; true },
+                "--algorithm" => { self.algorithm = parse_flag(flag, value)?; true },
+                "--braid-dead-ends" => { self.braid.dead_end_fraction = parse_flag(flag, value)?; true },
+                "--braid-walls" => { self.braid.wall_fraction = parse_flag(flag, value)?; true },
+                "--tick-rate" => { self.tick_rate = parse_flag(flag, value)?; true },
+                _ => false
+            };
+            index += if consumed { 2 } else { 1 };
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.maze_x < 1 || self.maze_y < 1 {
+            return Err(format!("Maze must be at least 1x1, got {}x{}", self.maze_x, self.maze_y));
+        }
+        if self.maze_scale <= 0. {
+            return Err(format!("Maze scale must be positive, got {}", self.maze_scale));
+        }
+        if self.tick_rate <= 0. {
+            return Err(format!("Tick rate must be positive, got {}", self.tick_rate));
+        }
+        Ok(())
+    }
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: Option<&str>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
@@ -1,9 +1,10 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{consts, position::Position};
 
 #[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum MazeDirection {
     NORTH,
     EAST,
@@ -55,21 +56,23 @@ impl MazeDirection {
         }
     }
 
-    pub fn get_wall_position_for_cell(&self) -> Vec3 {
+    pub fn get_wall_position_for_cell(&self, maze_scale: f32) -> Vec3 {
+        let half_cell = maze_scale / 2.;
         match &self {
-            MazeDirection::EAST => Vec3::new(2.5 - consts::WALL_THICKNESS, 2.5, 0.0),
-            MazeDirection::NORTH => Vec3::new(-2.5, 2.5 - consts::WALL_THICKNESS, 0.),
-            MazeDirection::WEST => Vec3::new(-2.5 + consts::WALL_THICKNESS, -2.5, 0.0),
-            MazeDirection::SOUTH => Vec3::new(2.5, -2.5 + consts::WALL_THICKNESS, 0.0),
+            MazeDirection::EAST => Vec3::new(half_cell - consts::WALL_THICKNESS, half_cell, 0.0),
+            MazeDirection::NORTH => Vec3::new(-half_cell, half_cell - consts::WALL_THICKNESS, 0.),
+            MazeDirection::WEST => Vec3::new(-half_cell + consts::WALL_THICKNESS, -half_cell, 0.0),
+            MazeDirection::SOUTH => Vec3::new(half_cell, -half_cell + consts::WALL_THICKNESS, 0.0),
         }
     }
 
-    pub fn get_door_position_for_cell(&self) -> Vec3 {
+    pub fn get_door_position_for_cell(&self, maze_scale: f32) -> Vec3 {
+        let half_cell = maze_scale / 2.;
         match &self {
-            MazeDirection::EAST => Vec3::new(2.5 - consts::WALL_THICKNESS, 2.5, 0.0),
-            MazeDirection::NORTH => Vec3::new(-2.5, 2.5 - consts::WALL_THICKNESS, 0.),
-            MazeDirection::WEST => Vec3::new(-2.5 + consts::WALL_THICKNESS, -2.5, 0.0),
-            MazeDirection::SOUTH => Vec3::new(2.5, -2.5 + consts::WALL_THICKNESS, 0.0),
+            MazeDirection::EAST => Vec3::new(half_cell - consts::WALL_THICKNESS, half_cell, 0.0),
+            MazeDirection::NORTH => Vec3::new(-half_cell, half_cell - consts::WALL_THICKNESS, 0.),
+            MazeDirection::WEST => Vec3::new(-half_cell + consts::WALL_THICKNESS, -half_cell, 0.0),
+            MazeDirection::SOUTH => Vec3::new(half_cell, -half_cell + consts::WALL_THICKNESS, 0.0),
         }
     }
 
@@ -2,8 +2,10 @@ use std::f32::consts::FRAC_PI_2;
 
 use bevy::prelude::*;
 
-use super::maze_direction::MazeDirection;
-use crate::physics::collider::Collider;
+use bevy::math::bounding::Aabb2d;
+
+use super::{maze_config::MazeConfig, maze_direction::MazeDirection, maze_events::DoorStateChanged, room_links::RoomLinks};
+use crate::{consts, noise::NoiseEvent, physics::{collider::{Collider, ColliderShape}, interpolation::InterpolatedTransform}, position::Position};
 /**
  * Because a door is more complicated, I want to make sure I have the logic for it all in one place.
  * A door consists of two entities, the frame which is the parent, and the child that is the door itself.
@@ -13,23 +15,51 @@ use crate::physics::collider::Collider;
  * new(position: Position) -> Spawns a door at a position with the door closed, returns a new MazeDoor
  * get_door_render(??) -> gives instructions to build the 3D objects/entities that make up a maze door, maybe returns an Entity or a Bundle? 
  * open_door(swing_forward: boolean) -> opens the door.  If swing_forward is true, then we rotate the y axis 90 positive, if false then 90 negative
+ * close_door() -> swings the door back shut, doors do this by themselves a while after nobody is next to them
  * is_door_open() -> boolean to let later logic know if the door should be treated as passable or impassable
+ *
+ * Anything short of all the way open blocks the way, see get_aabb2ds
  */
 
+// Measured along the doorway model, before it's scaled up.  The door hangs on a hinge partway along the
+// doorway and fills the gap to the other side of the frame.  The doorway is scaled so DOORWAY_LENGTH spans
+// a whole cell (see get_doorway_scale), so these hold at any maze scale
+const DOOR_HINGE: f32 = 0.75;
+const DOOR_WIDTH: f32 = 1.0;
+const DOORWAY_LENGTH: f32 = 2.5;
+// half the thickness of the door in the world
+const DOOR_HALF_THICKNESS: f32 = 0.1;
+// the door is made of a few boxes along it so it can be blocked at any angle without blocking the whole corner
+const DOOR_COLLIDER_PIECES: usize = 4;
+// how long a door stays open after the last thing next to it leaves
+const DOOR_CLOSE_SECONDS: f32 = 6.0;
+// how far round a door swings to be all the way open, back against the wall, and how fast it gets there in radians a second
+const DOOR_OPEN_ANGLE: f32 = FRAC_PI_2;
+const DOOR_SWING_SPEED: f32 = 3.5;
+
 #[derive(PartialEq)]
 enum DoorState {
     Closed,
     OpeningForward,
     OpeningBackward,
-    Open
+    Open,
+    Closing
 }
 
+// marks the doorway frame a door hangs in, the bits of frame either side of the door block like walls do
+#[derive(Component)]
+pub struct MazeDoorway;
+
 #[derive(Component, Deref, DerefMut)]
 pub struct MazeDoor {
     #[deref]
     door_child: Entity,
     maze_direction: MazeDirection,
     state: DoorState,
+    // how far the door has swung from closed, positive is forward.  Kept here rather than read back from the
+    // Transform so a door that changes direction part way round always knows where it is
+    swing_angle: f32,
+    close_timer: Timer
 }
 
 impl MazeDoor {
@@ -45,10 +75,17 @@ impl MazeDoor {
         MazeDoor { 
             door_child: door,
             maze_direction,
-            state: DoorState::Closed
+            state: DoorState::Closed,
+            swing_angle: 0.0,
+            close_timer: Timer::from_seconds(DOOR_CLOSE_SECONDS, TimerMode::Once)
         }
     }
 
+    // how much the doorway model, and the door hanging in it, are scaled up to fit the cell
+    pub fn get_doorway_scale(maze_scale: f32) -> f32 {
+        maze_scale / DOORWAY_LENGTH
+    }
+
     pub fn get_door_child(&self) -> Entity {
         self.door_child
     }
@@ -60,18 +97,22 @@ impl MazeDoor {
         let door = commands.spawn( (
             SceneBundle {
                 scene: door,
-                transform: Transform::from_xyz(0.75,0.,0.0)
+                transform: Transform::from_xyz(DOOR_HINGE,0.,0.0)
                     .with_scale(Vec3::new(1.0, 1.0, 1.0)),
                 ..default()
             },
             Collider,
+            InterpolatedTransform::default(),
         )).id();
 
         door
 }
 
     pub fn open_door(&mut self, swing_forward: bool) {
-        if self.state == DoorState::Open { return };
+        self.close_timer.reset();
+        if self.state == DoorState::Open || self.state == DoorState::OpeningForward || self.state == DoorState::OpeningBackward { return };
+        // a door caught while closing goes back the way it was open, rather than swinging through into whatever is on the other side
+        let swing_forward = if self.swing_angle != 0.0 { self.swing_angle > 0.0 } else { swing_forward };
         if swing_forward {
             self.state = DoorState::OpeningForward
         } else {
@@ -79,6 +120,12 @@ impl MazeDoor {
         }
     }
 
+    pub fn close_door(&mut self) {
+        if self.state == DoorState::Open {
+            self.state = DoorState::Closing
+        }
+    }
+
     pub fn get_maze_direction(&self) -> &MazeDirection {
         &self.maze_direction
     }
@@ -86,24 +133,219 @@ impl MazeDoor {
     pub fn is_door_open(&self) -> bool {
         self.state == DoorState::Open
     }
+
+    /**
+     * The boxes the door takes up on the floor at the moment, going by where it has swung to.  Takes the door's
+     * transform in the world, which is the doorway's GlobalTransform times the door's own Transform.
+     * A door that is all the way open is out of the way against the wall, so it has none
+     */
+    pub fn get_aabb2ds(&self, door_transform: &GlobalTransform) -> Vec<Aabb2d> {
+        if self.is_door_open() {
+            return vec![];
+        }
+        let hinge = door_transform.translation().xz();
+        let end = door_transform.transform_point(Vec3::X * DOOR_WIDTH).xz();
+        Collider::get_segment_aabb2ds(hinge, end, DOOR_HALF_THICKNESS, DOOR_COLLIDER_PIECES)
+    }
+
+    // the frame either side of the gap the door fills
+    pub fn get_doorway_aabb2ds(doorway_transform: &GlobalTransform) -> Vec<Aabb2d> {
+        let point = |along: f32| doorway_transform.transform_point(Vec3::X * along).xz();
+        vec![
+            Collider::get_segment_aabb2ds(point(0.), point(DOOR_HINGE), consts::WALL_THICKNESS, 1),
+            Collider::get_segment_aabb2ds(point(DOOR_HINGE + DOOR_WIDTH), point(DOORWAY_LENGTH), consts::WALL_THICKNESS, 1)
+        ].concat()
+    }
 }
 
-pub fn door_open_system(time: Res<Time>, mut door_query: Query<(&mut MazeDoor, &mut Transform)>) {
-    let door_open_speed: f32 = 3.5;
-    let door_open_max: f32 = 7.5;
-    for (mut door, mut door_transform) in door_query.iter_mut() {
+/**
+ * Doors open for anything that can bump into them (the player or a monster) in the cell on either side,
+ * swinging away from it, and close again once nothing has been next to them for a while.
+ * They count as closed, for the room links and anything listening for DoorStateChanged, from the moment they start closing
+ * until they are all the way open again.
+ */
+pub fn door_open_system(
+    time: Res<Time>,
+    mut door_query: Query<(&mut MazeDoor, &mut Transform, &GlobalTransform)>,
+    movers: Query<&Transform, (With<ColliderShape>, Without<MazeDoor>)>,
+    mut room_links: ResMut<RoomLinks>,
+    mut door_state_writer: EventWriter<DoorStateChanged>,
+    mut noise_writer: EventWriter<NoiseEvent>,
+    config: Res<MazeConfig>
+) {
+    let swing = DOOR_SWING_SPEED * time.delta_seconds();
+    let mover_positions: Vec<Position> = movers.iter().map(|mover| Position::get_from_transform(mover, config.maze_scale)).collect();
+    for (mut door, mut door_transform, door_global_transform) in door_query.iter_mut() {
+        let position = Position::get_from_transform(&door_global_transform.compute_transform(), config.maze_scale);
+        let other_side = &position + door.get_maze_direction().to_position_modifier();
+        let in_front = mover_positions.contains(&position);
+        if in_front || mover_positions.contains(&other_side) {
+            door.open_door(in_front);
+        }
+
         if door.state == DoorState::OpeningForward {
-            let rotation_angle = (door_open_speed * time.delta_seconds()).min(door_open_max);
-            door_transform.rotate_y(rotation_angle);
-            if door_transform.rotation.to_euler(EulerRot::XYZ).0 <= -1.0 {
+            door.swing_angle = (door.swing_angle + swing).min(DOOR_OPEN_ANGLE);
+            door_transform.rotation = Quat::from_rotation_y(door.swing_angle);
+            if door.swing_angle >= DOOR_OPEN_ANGLE {
                 door.state = DoorState::Open
             }
         } else if door.state == DoorState::OpeningBackward {
-            let rotation_angle = -1.0 * (door_open_speed * time.delta_seconds()).max(-door_open_max);
-            door_transform.rotate_y(rotation_angle);
-            if door_transform.rotation.to_euler(EulerRot::XYZ).0 >= 1.0 {
+            door.swing_angle = (door.swing_angle - swing).max(-DOOR_OPEN_ANGLE);
+            door_transform.rotation = Quat::from_rotation_y(door.swing_angle);
+            if door.swing_angle <= -DOOR_OPEN_ANGLE {
                 door.state = DoorState::Open
             }
+        } else if door.state == DoorState::Open {
+            if door.close_timer.tick(time.delta()).just_finished() {
+                door.close_door();
+                room_links.close_link_between(&position, &other_side);
+                door_state_writer.send(DoorStateChanged { position, other_side, is_open: false });
+            }
+            continue;
+        } else if door.state == DoorState::Closing {
+            // swing back to where it started
+            if swing >= door.swing_angle.abs() {
+                door.swing_angle = 0.0;
+                door.state = DoorState::Closed;
+                // and slams shut
+                noise_writer.send(NoiseEvent { position, loudness: consts::DOOR_LOUDNESS });
+            } else {
+                door.swing_angle -= swing * door.swing_angle.signum();
+            }
+            door_transform.rotation = Quat::from_rotation_y(door.swing_angle);
+            continue;
+        } else {
+            continue;
+        }
+
+        if door.state == DoorState::Open {
+            room_links.open_link_between(&position, &other_side);
+            door_state_writer.send(DoorStateChanged { position, other_side, is_open: true });
+            // the hinges creak as the door swings all the way open
+            noise_writer.send(NoiseEvent { position, loudness: consts::DOOR_LOUDNESS });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::maze::room_links::RoomPosition;
+
+    use super::*;
+
+    const TICK_SECONDS: f32 = 1. / 64.;
+
+    fn new_door() -> MazeDoor {
+        MazeDoor { door_child: Entity::PLACEHOLDER, maze_direction: MazeDirection::EAST, state: DoorState::Closed, swing_angle: 0.0, close_timer: Timer::from_seconds(DOOR_CLOSE_SECONDS, TimerMode::Once) }
+    }
+
+    // a door in cell (0, 0) into (1, 0), with the room link between them
+    fn new_world() -> (World, Entity) {
+        let mut world = World::new();
+        world.insert_resource(MazeConfig::default());
+        world.insert_resource(Time::<()>::default());
+        world.init_resource::<Events<DoorStateChanged>>();
+        world.init_resource::<Events<NoiseEvent>>();
+        let mut room_links = RoomLinks::new();
+        room_links.add_link(RoomPosition { room: 0, position: Position::new_from_i32(0, 0) }, RoomPosition { room: 1, position: Position::new_from_i32(1, 0) });
+        world.insert_resource(room_links);
+        let door = world.spawn((new_door(), Transform::default(), GlobalTransform::default())).id();
+        (world, door)
+    }
+
+    fn spawn_mover(world: &mut World, x: i32) -> Entity {
+        let maze_scale = world.resource::<MazeConfig>().maze_scale;
+        world.spawn((Transform::from_xyz(x as f32 * maze_scale, 0., 0.), ColliderShape::new(1., 1.))).id()
+    }
+
+    // runs the door a tick at a time until it's in the state asked for, or gives up after a while
+    fn run_until(world: &mut World, door: Entity, state: DoorState) -> bool {
+        for _ in 0..2000 {
+            if world.get::<MazeDoor>(door).unwrap().state == state {
+                return true;
+            }
+            run_ticks(world, 1);
+        }
+        false
+    }
+
+    fn run_ticks(world: &mut World, ticks: usize) {
+        for _ in 0..ticks {
+            world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(TICK_SECONDS));
+            world.run_system_once(door_open_system);
         }
     }
-}
\ No newline at end of file
+
+    fn is_link_open(world: &World) -> bool {
+        world.resource::<RoomLinks>().is_open_between(&Position::new_from_i32(0, 0), &Position::new_from_i32(1, 0))
+    }
+
+    #[test]
+    fn reopens_the_same_way_while_closing() {
+        let (mut world, door) = new_world();
+        let mover = spawn_mover(&mut world, 0);
+        assert!(run_until(&mut world, door, DoorState::Open));
+        assert_eq!(world.get::<MazeDoor>(door).unwrap().swing_angle, DOOR_OPEN_ANGLE);
+        assert!(is_link_open(&world));
+
+        world.despawn(mover);
+        assert!(run_until(&mut world, door, DoorState::Closing));
+        assert!(!is_link_open(&world));
+        run_ticks(&mut world, 10);
+        let part_closed = world.get::<MazeDoor>(door).unwrap().swing_angle;
+        assert!(part_closed > 0.0 && part_closed < DOOR_OPEN_ANGLE);
+
+        // coming from the other side would swing it backwards, but it's already open forwards
+        spawn_mover(&mut world, 1);
+        assert!(run_until(&mut world, door, DoorState::Open));
+        assert_eq!(world.get::<MazeDoor>(door).unwrap().swing_angle, DOOR_OPEN_ANGLE);
+        assert!(world.get::<Transform>(door).unwrap().rotation.abs_diff_eq(Quat::from_rotation_y(DOOR_OPEN_ANGLE), 0.0001));
+        assert!(is_link_open(&world));
+    }
+
+    #[test]
+    fn does_not_close_onto_something_in_the_way() {
+        let (mut world, door) = new_world();
+        let mover = spawn_mover(&mut world, 1);
+        assert!(run_until(&mut world, door, DoorState::Open));
+        assert_eq!(world.get::<MazeDoor>(door).unwrap().swing_angle, -DOOR_OPEN_ANGLE);
+
+        // stays open for as long as anything is next to it
+        run_ticks(&mut world, (3. * DOOR_CLOSE_SECONDS / TICK_SECONDS) as usize);
+        assert!(world.get::<MazeDoor>(door).unwrap().state == DoorState::Open);
+        assert!(is_link_open(&world));
+
+        // and closes all the way once it's gone
+        world.despawn(mover);
+        assert!(run_until(&mut world, door, DoorState::Closed));
+        assert_eq!(world.get::<MazeDoor>(door).unwrap().swing_angle, 0.0);
+        assert_eq!(world.get::<Transform>(door).unwrap().rotation, Quat::IDENTITY);
+        assert!(!is_link_open(&world));
+    }
+
+    // a doorway along the x axis from the origin, scaled to fit the cell like create_edge_entity does
+    fn doorway_transform(maze_scale: f32) -> GlobalTransform {
+        GlobalTransform::from(Transform::from_scale(Vec3::splat(MazeDoor::get_doorway_scale(maze_scale))))
+    }
+
+    #[test]
+    fn doorway_spans_the_cell_at_any_scale() {
+        for maze_scale in [5., 8., 3.] {
+            let doorway = doorway_transform(maze_scale);
+            let frame = MazeDoor::get_doorway_aabb2ds(&doorway);
+            assert!((frame[0].min.x - -consts::WALL_THICKNESS).abs() < 0.001, "scale {}: frame starts at {}", maze_scale, frame[0].min.x);
+            assert!((frame[1].max.x - (maze_scale + consts::WALL_THICKNESS)).abs() < 0.001, "scale {}: frame ends at {}", maze_scale, frame[1].max.x);
+
+            // a closed door fills the gap between the two bits of frame
+            let door = new_door();
+            let door_aabbs = door.get_aabb2ds(&doorway.mul_transform(Transform::from_xyz(DOOR_HINGE, 0., 0.)));
+            let door_min = door_aabbs.iter().map(|aabb| aabb.min.x).fold(f32::MAX, f32::min);
+            let door_max = door_aabbs.iter().map(|aabb| aabb.max.x).fold(f32::MIN, f32::max);
+            assert!(door_min <= frame[0].max.x && door_max >= frame[1].min.x, "scale {}: door {}..{} leaves a gap in the frame", maze_scale, door_min, door_max);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+use crate::position::Position;
+
+// Sent once the maze is generated, for every time two rooms joined into one along the way.
+// The ids are the ones the rooms had when they merged, before the layout compacted its rooms
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct RoomMerged {
+    pub kept_room: usize,
+    pub merged_room: usize
+}
+
+
+// Sent when a door finishes opening or closing, with the cells on either side of it
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct DoorStateChanged {
+    pub position: Position,
+    pub other_side: Position,
+    pub is_open: bool
+}
@@ -0,0 +1,293 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::position::Position;
+use crate::random::MazeSeed;
+use super::maze_cell_edge::EdgeType;
+use super::maze_direction::MazeDirection;
+use super::maze_layout::MazeLayout;
+
+const SVG_CELL_SIZE: i32 = 32;
+const SVG_MARGIN: i32 = 8;
+const SVG_ROOM_COLORS: [&str; 4] = ["#e8d8b0", "#c8d8e8", "#d8f0f0", "#f0d8c8"];
+
+/**
+ * Text versions of a MazeLayout, for looking at and archiving layouts outside of the game.
+ * Every format shows the room each cell belongs to and where the doors are.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Ascii,
+    Json,
+    Svg
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ascii" => Ok(ExportFormat::Ascii),
+            "json" => Ok(ExportFormat::Json),
+            "svg" => Ok(ExportFormat::Svg),
+            _ => Err(format!("Unknown export format: {}", name))
+        }
+    }
+}
+
+// A door as seen from the Doorway side, the InverseDoorway side is the cell in `direction`
+pub struct DoorPosition {
+    pub position: Position,
+    pub direction: MazeDirection,
+    pub from_room: usize,
+    pub to_room: usize
+}
+
+pub fn export_layout(layout: &MazeLayout, format: ExportFormat, room_names: &[&str], seed: Option<MazeSeed>) -> String {
+    match format {
+        ExportFormat::Ascii => to_ascii(layout, room_names),
+        ExportFormat::Json => to_json(layout, room_names, seed),
+        ExportFormat::Svg => to_svg(layout)
+    }
+}
+
+pub fn get_door_positions(layout: &MazeLayout) -> Vec<DoorPosition> {
+    let mut doors = vec![];
+    for position in layout.get_positions() {
+        let Some(cell) = layout.get_cell(&position) else { continue };
+        for direction in get_directions() {
+            if cell.get_edge_type(&direction) != Some(EdgeType::Doorway) {
+                continue;
+            }
+            let to_room = layout.get_cell(&(&position + direction.to_position_modifier()))
+                .map(|neighbour| neighbour.get_room_index())
+                .unwrap_or(cell.get_room_index());
+            doors.push(DoorPosition { position, direction, from_room: cell.get_room_index(), to_room });
+        }
+    }
+    doors
+}
+
+/**
+ * +---+---+
+ * | 0   0 |
+ * +-D-+---+
+ * | 1 D 2 |
+ * +---+---+
+ * Walls are drawn with - and |, doors with D, and each cell shows its room id.
+ */
+pub fn to_ascii(layout: &MazeLayout, room_names: &[&str]) -> String {
+    let room_count = layout.get_maze_rooms().get_room_count();
+    let cell_width = (room_count.max(1) - 1).to_string().len().max(1) + 2;
+
+    let mut output = String::new();
+    for y in 0..layout.size_y {
+        output.push_str(&get_ascii_border(layout, y, MazeDirection::NORTH, cell_width));
+        for x in 0..layout.size_x {
+            let position = Position::new_from_i32(x, y);
+            output.push(get_ascii_vertical_edge(layout, &position, MazeDirection::WEST));
+            let room = layout.get_cell(&position).map_or(String::from("?"), |cell| cell.get_room_index().to_string());
+            output.push_str(&format!("{:^width$}", room, width = cell_width));
+        }
+        output.push(get_ascii_vertical_edge(layout, &Position::new_from_i32(layout.size_x - 1, y), MazeDirection::EAST));
+        output.push('\n');
+    }
+    output.push_str(&get_ascii_border(layout, layout.size_y - 1, MazeDirection::SOUTH, cell_width));
+
+    output.push('\n');
+    for room in 0..room_count {
+        let settings_index = layout.get_maze_rooms().get_settings_index_from_room_index(room);
+        let _ = writeln!(output, "Room {}: {}", room, get_room_name(room_names, settings_index));
+    }
+    for door in get_door_positions(layout) {
+        let _ = writeln!(output, "Door at ({}, {}) {:?}: room {} -> room {}", door.position.x, door.position.y, door.direction, door.from_room, door.to_room);
+    }
+    output
+}
+
+fn get_ascii_border(layout: &MazeLayout, y: i32, side: MazeDirection, cell_width: usize) -> String {
+    let mut line = String::new();
+    for x in 0..layout.size_x {
+        line.push('+');
+        let edge_type = layout.get_cell(&Position::new_from_i32(x, y)).and_then(|cell| cell.get_edge_type(&side));
+        let edge = match edge_type {
+            Some(EdgeType::Wall) => "-".repeat(cell_width),
+            Some(EdgeType::Doorway) | Some(EdgeType::InverseDoorway) => format!("{:-^width$}", "D", width = cell_width),
+            None => " ".repeat(cell_width)
+        };
+        line.push_str(&edge);
+    }
+    line.push_str("+\n");
+    line
+}
+
+fn get_ascii_vertical_edge(layout: &MazeLayout, position: &Position, side: MazeDirection) -> char {
+    match layout.get_cell(position).and_then(|cell| cell.get_edge_type(&side)) {
+        Some(EdgeType::Wall) => '|',
+        Some(EdgeType::Doorway) | Some(EdgeType::InverseDoorway) => 'D',
+        None => ' '
+    }
+}
+
+#[derive(Serialize)]
+struct JsonLayout {
+    seed: Option<String>,
+    size_x: i32,
+    size_y: i32,
+    rooms: Vec<JsonRoom>,
+    cells: Vec<JsonCell>,
+    doors: Vec<JsonDoor>
+}
+
+#[derive(Serialize)]
+struct JsonRoom {
+    id: usize,
+    settings_index: usize,
+    settings_name: String
+}
+
+#[derive(Serialize)]
+struct JsonCell {
+    x: i32,
+    y: i32,
+    room: usize,
+    north: &'static str,
+    east: &'static str,
+    south: &'static str,
+    west: &'static str
+}
+
+#[derive(Serialize)]
+struct JsonDoor {
+    x: i32,
+    y: i32,
+    direction: String,
+    from_room: usize,
+    to_room: usize
+}
+
+pub fn to_json(layout: &MazeLayout, room_names: &[&str], seed: Option<MazeSeed>) -> String {
+    let rooms = (0..layout.get_maze_rooms().get_room_count()).map(|room| {
+        let settings_index = layout.get_maze_rooms().get_settings_index_from_room_index(room);
+        JsonRoom { id: room, settings_index, settings_name: String::from(get_room_name(room_names, settings_index)) }
+    }).collect();
+
+    let cells = layout.get_positions().iter().filter_map(|position| {
+        let cell = layout.get_cell(position)?;
+        Some(JsonCell {
+            x: position.x as i32,
+            y: position.y as i32,
+            room: cell.get_room_index(),
+            north: get_edge_name(cell.get_edge_type(&MazeDirection::NORTH)),
+            east: get_edge_name(cell.get_edge_type(&MazeDirection::EAST)),
+            south: get_edge_name(cell.get_edge_type(&MazeDirection::SOUTH)),
+            west: get_edge_name(cell.get_edge_type(&MazeDirection::WEST))
+        })
+    }).collect();
+
+    let doors = get_door_positions(layout).into_iter().map(|door| JsonDoor {
+        x: door.position.x as i32,
+        y: door.position.y as i32,
+        direction: format!("{:?}", door.direction).to_lowercase(),
+        from_room: door.from_room,
+        to_room: door.to_room
+    }).collect();
+
+    let json_layout = JsonLayout {
+        seed: seed.map(|seed| seed.to_code()),
+        size_x: layout.size_x,
+        size_y: layout.size_y,
+        rooms,
+        cells,
+        doors
+    };
+    serde_json::to_string_pretty(&json_layout).expect("maze layout should always serialize")
+}
+
+pub fn to_svg(layout: &MazeLayout) -> String {
+    let width = layout.size_x * SVG_CELL_SIZE + SVG_MARGIN * 2;
+    let height = layout.size_y * SVG_CELL_SIZE + SVG_MARGIN * 2;
+
+    let mut output = String::new();
+    let _ = writeln!(output, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, width, height, width, height);
+    let _ = writeln!(output, r#"<rect width="{}" height="{}" fill="white"/>"#, width, height);
+
+    for position in layout.get_positions() {
+        let Some(cell) = layout.get_cell(&position) else { continue };
+        let (left, top) = get_svg_corner(&position);
+        let settings_index = layout.get_maze_rooms().get_settings_index_from_room_index(cell.get_room_index());
+        let _ = writeln!(output, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            left, top, SVG_CELL_SIZE, SVG_CELL_SIZE, SVG_ROOM_COLORS[settings_index % SVG_ROOM_COLORS.len()]);
+        let _ = writeln!(output, r#"<text x="{}" y="{}" font-family="monospace" font-size="10" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+            left + SVG_CELL_SIZE / 2, top + SVG_CELL_SIZE / 2, cell.get_room_index());
+    }
+
+    // draw each cell's north and west edges, plus the south and east edges along the outside
+    for position in layout.get_positions() {
+        let Some(cell) = layout.get_cell(&position) else { continue };
+        let mut sides = vec![MazeDirection::NORTH, MazeDirection::WEST];
+        if position.y as i32 == layout.size_y - 1 {
+            sides.push(MazeDirection::SOUTH);
+        }
+        if position.x as i32 == layout.size_x - 1 {
+            sides.push(MazeDirection::EAST);
+        }
+        for side in sides {
+            output.push_str(&get_svg_edge(&position, side, cell.get_edge_type(&side)));
+        }
+    }
+
+    output.push_str("</svg>\n");
+    output
+}
+
+fn get_svg_corner(position: &Position) -> (i32, i32) {
+    (SVG_MARGIN + position.x as i32 * SVG_CELL_SIZE, SVG_MARGIN + position.y as i32 * SVG_CELL_SIZE)
+}
+
+fn get_svg_edge(position: &Position, side: MazeDirection, edge_type: Option<EdgeType>) -> String {
+    let (left, top) = get_svg_corner(position);
+    let (right, bottom) = (left + SVG_CELL_SIZE, top + SVG_CELL_SIZE);
+    let (x1, y1, x2, y2) = match side {
+        MazeDirection::NORTH => (left, top, right, top),
+        MazeDirection::EAST => (right, top, right, bottom),
+        MazeDirection::SOUTH => (left, bottom, right, bottom),
+        MazeDirection::WEST => (left, top, left, bottom)
+    };
+
+    match edge_type {
+        Some(EdgeType::Wall) => format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"2\"/>\n", x1, y1, x2, y2),
+        Some(EdgeType::Doorway) | Some(EdgeType::InverseDoorway) => {
+            // wall stubs either side of a brown door in the middle third
+            let third_x = (x2 - x1) / 3;
+            let third_y = (y2 - y1) / 3;
+            format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"2\"/>\n\
+                 <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#8b4513\" stroke-width=\"4\"/>\n\
+                 <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"2\"/>\n",
+                x1, y1, x1 + third_x, y1 + third_y,
+                x1 + third_x, y1 + third_y, x2 - third_x, y2 - third_y,
+                x2 - third_x, y2 - third_y, x2, y2)
+        },
+        None => String::new()
+    }
+}
+
+fn get_directions() -> [MazeDirection; 4] {
+    [MazeDirection::NORTH, MazeDirection::EAST, MazeDirection::SOUTH, MazeDirection::WEST]
+}
+
+pub(crate) fn get_edge_name(edge_type: Option<EdgeType>) -> &'static str {
+    match edge_type {
+        Some(EdgeType::Wall) => "wall",
+        Some(EdgeType::Doorway) => "doorway",
+        Some(EdgeType::InverseDoorway) => "inverse_doorway",
+        None => "passage"
+    }
+}
+
+fn get_room_name<'a>(room_names: &[&'a str], settings_index: usize) -> &'a str {
+    room_names.get(settings_index).copied().unwrap_or("Unknown")
+}
@@ -0,0 +1,358 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs;
+
+use rand::Rng;
+
+use crate::position::Position;
+use super::maze_config::MazeConfig;
+use super::maze_layout::MazeLayout;
+
+/**
+ * Hand drawn levels.  The maze is drawn as a grid where every cell and every edge gets one character:
+ *
+ * # a kitchen and a bathroom, start in the top left and exit in the bottom right
+ * +-+-+-+
+ * |S a|b|
+ * +-+ + +
+ * |a aDE|
+ * +-+-+-+
+ * a = Kitchen
+ * b = Bathroom
+ *
+ * Corners are '+'.  Between cells, '-' or '|' is a wall, 'D' is a door and a space is a passage.
+ * Each cell is a lowercase room letter, 'S' for the start, 'E' for the exit, or a space or '.' to take the
+ * room of whatever it is open to.  Cells joined by passages are one room, so they can't have different letters,
+ * and a door always has to be between two different rooms.  The outside of the maze has to be all wall.
+ *
+ * After the grid, lines like "a = Kitchen" pick the room settings by name.  Rooms without one use the settings
+ * at their letter's position in the alphabet.  Lines starting with '#' are comments.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ImportedEdge {
+    Wall,
+    Door,
+    Passage
+}
+
+// The drawn grid, with row and column counted from the first grid line so cell (x, y) is at (2y + 1, 2x + 1)
+struct Grid {
+    rows: Vec<Vec<char>>,
+    first_line: usize
+}
+
+impl Grid {
+    fn get_char(&self, row: usize, column: usize) -> char {
+        self.rows.get(row).and_then(|line| line.get(column)).copied().unwrap_or(' ')
+    }
+
+    // 1 based, like an editor shows them
+    fn get_line_and_column(&self, row: usize, column: usize) -> (usize, usize) {
+        (self.first_line + row + 1, column + 1)
+    }
+
+    fn error(&self, row: usize, column: usize, message: String) -> ImportError {
+        let (line, column) = self.get_line_and_column(row, column);
+        ImportError { line, column, message }
+    }
+
+    fn get_edge(&self, row: usize, column: usize) -> Result<ImportedEdge, ImportError> {
+        let wall = if row % 2 == 0 { '-' } else { '|' };
+        match self.get_char(row, column) {
+            'D' => Ok(ImportedEdge::Door),
+            ' ' => Ok(ImportedEdge::Passage),
+            found if found == wall => Ok(ImportedEdge::Wall),
+            found => Err(self.error(row, column, format!("expected '{}' (wall), 'D' (door) or ' ' (passage) but found '{}'", wall, found)))
+        }
+    }
+}
+
+pub fn import_ascii_layout_from_file(path: &str, config: &MazeConfig, room_names: &[&str], rng: &mut impl Rng) -> Result<MazeLayout, String> {
+    let contents = fs::read_to_string(path).map_err(|error| format!("Could not read level {}: {}", path, error))?;
+    import_ascii_layout(&contents, config, room_names, rng).map_err(|error| format!("{}: {}", path, error))
+}
+
+// Walls get the same random wall lights a generated maze would, using the config's wall_light_probability
+pub fn import_ascii_layout(text: &str, config: &MazeConfig, room_names: &[&str], rng: &mut impl Rng) -> Result<MazeLayout, ImportError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let grid = read_grid(&lines)?;
+    let size_x = (grid.rows[0].len() / 2) as i32;
+    let size_y = (grid.rows.len() / 2) as i32;
+
+    check_corners_and_border(&grid, size_x as usize, size_y as usize)?;
+
+    // read every cell up front so the room flood fill can look at its neighbours
+    let mut cells: HashMap<(usize, usize), char> = HashMap::new();
+    let mut start = None;
+    let mut exit = None;
+    for y in 0..size_y as usize {
+        for x in 0..size_x as usize {
+            let (row, column) = (2 * y + 1, 2 * x + 1);
+            let cell = grid.get_char(row, column);
+            match cell {
+                'a'..='z' | ' ' | '.' => {},
+                'S' | 'E' => {
+                    let marker = if cell == 'S' { &mut start } else { &mut exit };
+                    if marker.is_some() {
+                        return Err(grid.error(row, column, format!("there can only be one '{}'", cell)));
+                    }
+                    *marker = Some(Position::new_from_i32(x as i32, y as i32));
+                },
+                _ => return Err(grid.error(row, column, format!("expected a room letter, 'S', 'E' or ' ' but found '{}'", cell)))
+            }
+            cells.insert((x, y), cell);
+        }
+    }
+    let start = start.ok_or(grid.error(0, 0, String::from("the maze has no start, mark one cell with 'S'")))?;
+    let exit = exit.ok_or(grid.error(0, 0, String::from("the maze has no exit, mark one cell with 'E'")))?;
+
+    let (cell_rooms, room_letters) = find_rooms(&grid, &cells, size_x as usize, size_y as usize)?;
+    let legend = read_legend(&lines[grid.first_line + grid.rows.len()..], grid.first_line + grid.rows.len(), &room_letters, room_names)?;
+
+    let layout_config = MazeConfig { maze_x: size_x, maze_y: size_y, ..config.clone() };
+    let mut layout = MazeLayout::new(&layout_config, room_names.len());
+    for letter in &room_letters {
+        let settings_index = legend.get(letter).copied().unwrap_or((*letter as usize - 'a' as usize) % room_names.len());
+        layout.add_room(settings_index);
+    }
+    for y in 0..size_y {
+        for x in 0..size_x {
+            layout.add_cell(&Position::new_from_i32(x, y), cell_rooms[&(x as usize, y as usize)]);
+        }
+    }
+
+    for y in 0..size_y {
+        for x in 0..size_x {
+            let position = Position::new_from_i32(x, y);
+            let (row, column) = (2 * y as usize + 1, 2 * x as usize + 1);
+            // each cell adds its east and south edges, plus the outside walls on the north and west
+            let neighbours = [
+                (Position::new_from_i32(x + 1, y), row, column + 1),
+                (Position::new_from_i32(x, y + 1), row + 1, column),
+                (Position::new_from_i32(x, y - 1), row - 1, column),
+                (Position::new_from_i32(x - 1, y), row, column - 1)
+            ];
+            for (neighbour, edge_row, edge_column) in neighbours {
+                let inside = layout.contains_position(&neighbour);
+                if inside && (neighbour.y < position.y || neighbour.x < position.x) {
+                    continue;
+                }
+                match grid.get_edge(edge_row, edge_column)? {
+                    ImportedEdge::Wall => layout.add_wall(&position, &neighbour, rng),
+                    ImportedEdge::Door => layout.add_door(&position, &neighbour, rng),
+                    ImportedEdge::Passage => layout.add_passage(&position, &neighbour, rng)
+                }
+            }
+        }
+    }
+
+    layout.set_start_and_exit(start, exit);
+    Ok(layout)
+}
+
+fn read_grid(lines: &[&str]) -> Result<Grid, ImportError> {
+    let first_line = lines.iter().position(|line| line.starts_with('+'))
+        .ok_or(ImportError { line: 1, column: 1, message: String::from("no maze found, the grid starts with a line beginning with '+'") })?;
+    let rows: Vec<Vec<char>> = lines[first_line..].iter()
+        .take_while(|line| line.starts_with('+') || line.starts_with('|'))
+        .map(|line| line.trim_end().chars().collect())
+        .collect();
+    let grid = Grid { rows, first_line };
+
+    let width = grid.rows[0].len();
+    if width < 3 || width % 2 == 0 {
+        return Err(grid.error(0, width, String::from("the top border should look like +-+-+, one '-' per cell")));
+    }
+    if grid.rows.len() < 3 || grid.rows.len() % 2 == 0 {
+        let last_row = grid.rows.len() - 1;
+        return Err(grid.error(last_row, 0, String::from("the grid has to end with a border line like +-+-+")));
+    }
+    for (row, line) in grid.rows.iter().enumerate() {
+        if line.len() > width {
+            return Err(grid.error(row, width, format!("line is longer than the top border ({} characters)", width)));
+        }
+    }
+    Ok(grid)
+}
+
+fn check_corners_and_border(grid: &Grid, size_x: usize, size_y: usize) -> Result<(), ImportError> {
+    for row in (0..=2 * size_y).step_by(2) {
+        for column in (0..=2 * size_x).step_by(2) {
+            if grid.get_char(row, column) != '+' {
+                return Err(grid.error(row, column, format!("expected '+' at a corner but found '{}'", grid.get_char(row, column))));
+            }
+        }
+    }
+
+    let mut border = vec![];
+    for x in 0..size_x {
+        border.push((0, 2 * x + 1));
+        border.push((2 * size_y, 2 * x + 1));
+    }
+    for y in 0..size_y {
+        border.push((2 * y + 1, 0));
+        border.push((2 * y + 1, 2 * size_x));
+    }
+    for (row, column) in border {
+        if grid.get_edge(row, column)? != ImportedEdge::Wall {
+            return Err(grid.error(row, column, String::from("the outside of the maze has to be a wall")));
+        }
+    }
+    Ok(())
+}
+
+// Flood fills through passages to find the rooms.  Returns the room index of every cell, and each room's letter
+fn find_rooms(grid: &Grid, cells: &HashMap<(usize, usize), char>, size_x: usize, size_y: usize) -> Result<(HashMap<(usize, usize), usize>, Vec<char>), ImportError> {
+    let mut cell_rooms: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut room_letters: Vec<char> = vec![];
+    // where each letter was first seen, so a room split in two can point back at the other half
+    let mut letter_positions: HashMap<char, (usize, usize)> = HashMap::new();
+
+    for y in 0..size_y {
+        for x in 0..size_x {
+            if cell_rooms.contains_key(&(x, y)) {
+                continue;
+            }
+            let room_index = room_letters.len();
+            let mut letter: Option<(char, usize, usize)> = None;
+            let mut queue = VecDeque::from([(x, y)]);
+            cell_rooms.insert((x, y), room_index);
+
+            while let Some((cell_x, cell_y)) = queue.pop_front() {
+                let (row, column) = (2 * cell_y + 1, 2 * cell_x + 1);
+                let cell = cells[&(cell_x, cell_y)];
+                if cell.is_ascii_lowercase() && letter.is_none() {
+                    letter = Some((cell, row, column));
+                }
+
+                let mut neighbours = vec![];
+                if cell_x + 1 < size_x { neighbours.push(((cell_x + 1, cell_y), row, column + 1)); }
+                if cell_y + 1 < size_y { neighbours.push(((cell_x, cell_y + 1), row + 1, column)); }
+                if cell_x > 0 { neighbours.push(((cell_x - 1, cell_y), row, column - 1)); }
+                if cell_y > 0 { neighbours.push(((cell_x, cell_y - 1), row - 1, column)); }
+
+                for (neighbour, edge_row, edge_column) in neighbours {
+                    if grid.get_edge(edge_row, edge_column)? != ImportedEdge::Passage {
+                        continue;
+                    }
+                    let neighbour_cell = cells[&neighbour];
+                    if let Some((room_letter, _, _)) = letter {
+                        if neighbour_cell.is_ascii_lowercase() && neighbour_cell != room_letter {
+                            return Err(grid.error(edge_row, edge_column,
+                                format!("passage between room '{}' and room '{}', they need a wall or door between them", room_letter, neighbour_cell)));
+                        }
+                    }
+                    if !cell_rooms.contains_key(&neighbour) {
+                        cell_rooms.insert(neighbour, room_index);
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            let (room_letter, row, column) = letter.ok_or(grid.error(2 * y + 1, 2 * x + 1, String::from("this open space has no room letter")))?;
+            if let Some((other_row, other_column)) = letter_positions.get(&room_letter) {
+                let (other_line, other_column) = grid.get_line_and_column(*other_row, *other_column);
+                return Err(grid.error(row, column,
+                    format!("room '{}' is split in two, it is also at line {}, column {} but there's no passage between them", room_letter, other_line, other_column)));
+            }
+            letter_positions.insert(room_letter, (row, column));
+            room_letters.push(room_letter);
+        }
+    }
+
+    // every room is known now, so a door inside one can be caught
+    for y in 0..size_y {
+        for x in 0..size_x {
+            let (row, column) = (2 * y + 1, 2 * x + 1);
+            for (neighbour, edge_row, edge_column) in [((x + 1, y), row, column + 1), ((x, y + 1), row + 1, column)] {
+                if neighbour.0 >= size_x || neighbour.1 >= size_y {
+                    continue;
+                }
+                if grid.get_edge(edge_row, edge_column)? == ImportedEdge::Door && cell_rooms[&(x, y)] == cell_rooms[&neighbour] {
+                    return Err(grid.error(edge_row, edge_column,
+                        format!("door inside room '{}', doors have to be between two rooms", room_letters[cell_rooms[&(x, y)]])));
+                }
+            }
+        }
+    }
+
+    Ok((cell_rooms, room_letters))
+}
+
+// "a = Kitchen" lines after the grid
+fn read_legend(lines: &[&str], first_line: usize, room_letters: &[char], room_names: &[&str]) -> Result<HashMap<char, usize>, ImportError> {
+    let mut legend = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        let line_number = first_line + index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((letter, name)) = trimmed.split_once('=') else {
+            return Err(ImportError { line: line_number, column: 1, message: format!("expected a room like \"a = {}\"", room_names[0]) });
+        };
+        let letter = letter.trim();
+        let name = name.trim();
+        let name_column = line.find(name).unwrap_or(0) + 1;
+        let mut letter_chars = letter.chars();
+        let (Some(letter), None) = (letter_chars.next(), letter_chars.next()) else {
+            return Err(ImportError { line: line_number, column: 1, message: format!("expected a single room letter but found '{}'", letter) });
+        };
+        if !room_letters.contains(&letter) {
+            return Err(ImportError { line: line_number, column: line.find(letter).unwrap_or(0) + 1, message: format!("room '{}' is not in the maze", letter) });
+        }
+        let settings_index = room_names.iter().position(|room_name| *room_name == name)
+            .ok_or(ImportError { line: line_number, column: name_column, message: format!("unknown room settings '{}', expected one of: {}", name, room_names.join(", ")) })?;
+        legend.insert(letter, settings_index);
+    }
+    Ok(legend)
+}
+
+// Tests across the crate draw their mazes the same way --import reads them
+#[cfg(test)]
+pub(crate) mod test_layouts {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::maze::maze_config::MazeConfig;
+    use crate::maze::maze_layout::MazeLayout;
+    use crate::maze::maze_room::ROOM_SETTINGS_NAMES;
+
+    use super::import_ascii_layout;
+
+    // one room, with a wall that makes the way from S to E go the long way round
+    pub const HAIRPIN: &str = "
++-+-+-+
+|S a a|
++-+-+ +
+|E a a|
++-+-+-+
+";
+
+    // two ways from room a to room e: a short one through d, and a long one through b and c
+    pub const FIVE_ROOMS: &str = "
++-+-+-+-+-+
+|S aDb bDc|
++ +-+-+-+D+
+|a aDdDe E|
++-+-+-+-+-+
+";
+
+    pub fn import(text: &str) -> MazeLayout {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        import_ascii_layout(text, &MazeConfig::default(), &ROOM_SETTINGS_NAMES, &mut rng).unwrap()
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, VecDeque};
+
+use rand::Rng;
+
+use crate::position::Position;
+use super::generators::maze_generator::MazeGenerator;
+use super::maze_config::MazeConfig;
+use super::maze_cell::MazeCell;
+use super::maze_cell_edge::EdgeType;
+use super::maze_direction::MazeDirection;
+use super::maze_events::RoomMerged;
+use super::maze_room::MazeRooms;
+use super::room_links::{RoomLinks, RoomPosition};
+
+/**
+ * The layout is the pure data side of a maze: which cells exist, which room each cell belongs to,
+ * and what sits on each edge between cells.  It does not know about materials, models or the ECS,
+ * so it can be generated, inspected and compared without spinning up an App.
+ *
+ * Rooms in the layout only carry a settings index, the Maze attaches the actual MazeRoomSettings afterwards.
+ */
+#[derive(Default, Clone)]
+pub struct MazeLayout {
+    pub size_x: i32,
+    pub size_y: i32,
+    door_probability: f32,
+    wall_light_probability: f32,
+    maze_rooms: MazeRooms,
+    // where the player starts and the cell they have to reach to win
+    start: Position,
+    exit: Position,
+    // every merge made while generating, for the RoomMerged events
+    room_merges: Vec<RoomMerged>,
+    // one link for every door, kept up to date as doors are added, until the game takes them with take_room_links
+    room_links: RoomLinks
+}
+
+impl MazeLayout {
+    pub fn new(config: &MazeConfig, settings_count: usize) -> Self {
+        MazeLayout {
+            size_x: config.maze_x,
+            size_y: config.maze_y,
+            door_probability: config.door_probability,
+            wall_light_probability: config.wall_light_probability,
+            maze_rooms: MazeRooms::new(settings_count, config.maze_x, config.maze_y),
+            start: Position::new(0., 0.),
+            exit: Position::new_from_i32(config.maze_x - 1, config.maze_y - 1),
+            room_merges: vec![],
+            room_links: RoomLinks::new()
+        }
+    }
+
+    // an empty layout to fill in by hand, like when loading a saved maze
+    pub fn with_size(size_x: i32, size_y: i32, settings_count: usize) -> Self {
+        MazeLayout {
+            size_x,
+            size_y,
+            door_probability: 0.,
+            wall_light_probability: 0.,
+            maze_rooms: MazeRooms::new(settings_count, size_x, size_y),
+            start: Position::new(0., 0.),
+            exit: Position::new_from_i32(size_x - 1, size_y - 1),
+            room_merges: vec![],
+            room_links: RoomLinks::new()
+        }
+    }
+
+    pub fn generate(config: &MazeConfig, settings_count: usize, generator: &impl MazeGenerator, rng: &mut impl Rng) -> Self {
+        let mut layout = MazeLayout::new(config, settings_count);
+        generator.generate(&mut layout, rng);
+        layout.maze_rooms.compact_rooms();
+        layout.room_links.refresh_rooms(&layout.maze_rooms);
+        layout
+    }
+
+    pub fn create_room_and_return_index(&mut self, index_to_exclude: usize, rng: &mut impl Rng) -> usize {
+        self.maze_rooms.create_room_and_return_index(index_to_exclude, rng)
+    }
+
+    pub fn add_room(&mut self, settings_index: usize) -> usize {
+        self.maze_rooms.add_room(settings_index)
+    }
+
+    pub fn get_settings_index_for_cell(&self, cell: &MazeCell) -> usize {
+        self.maze_rooms.get_settings_index_from_room_index(cell.get_room_index())
+    }
+
+    pub fn merge_rooms(&mut self, room_a: usize, room_b: usize) {
+        if let Some(room_merge) = self.maze_rooms.merge_rooms(room_a, room_b) {
+            self.room_merges.push(room_merge);
+        }
+    }
+
+    pub fn take_room_merges(&mut self) -> Vec<RoomMerged> {
+        std::mem::take(&mut self.room_merges)
+    }
+
+    // Grows the maze from a cell into an empty neighbouring cell, either continuing the current room
+    // through a passage or starting a new room behind a door
+    pub fn carve_into_new_cell(&mut self, position: &Position, new_position: &Position, rng: &mut impl Rng) {
+        let current_room_index = self.get_cell(position).expect("Current cell not in maze somehow").get_room_index();
+        let index_to_exclude = self.maze_rooms.get_settings_index_from_room_index(current_room_index);
+        if rng.gen_range(0. .. 1.) < self.door_probability {
+            let new_room_index = self.maze_rooms.create_room_and_return_index(index_to_exclude, rng);
+            self.add_cell(new_position, new_room_index);
+            self.add_door(position, new_position, rng);
+        } else {
+            self.add_cell(new_position, current_room_index);
+            self.add_passage(position, new_position, rng);
+        }
+    }
+
+    // Once every cell exists, any edge that was never carved becomes a wall, unless it sits between two
+    // rooms that share settings, in which case the rooms merge and it is left open like the growing tree does
+    pub fn close_remaining_edges(&mut self, rng: &mut impl Rng) {
+        for y in 0..self.size_y {
+            for x in 0..self.size_x {
+                let position = Position::new_from_i32(x, y);
+                for direction_index in 0..4 {
+                    let direction = MazeDirection::get_direction_from_index(direction_index);
+                    let Some(cell) = self.get_cell(&position) else { continue };
+                    if cell.has_edge(&direction) {
+                        continue;
+                    }
+                    let new_position = &position + direction.to_position_modifier();
+                    match self.get_cell(&new_position) {
+                        Some(neighbour) if self.get_settings_index_for_cell(cell) == self.get_settings_index_for_cell(neighbour) => {
+                            self.merge_rooms(cell.get_room_index(), neighbour.get_room_index());
+                            self.add_passage(&position, &new_position, rng);
+                        },
+                        _ => {
+                            self.add_wall(&position, &new_position, rng);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Knocks through an existing wall.  Inside a room this leaves a passage, across a room boundary it becomes a
+    // door so the rooms stay separate and the Doorway/InverseDoorway pair stays consistent
+    pub fn open_wall(&mut self, position: &Position, new_position: &Position, rng: &mut impl Rng) {
+        let maze_direction = MazeDirection::get_direction_position_from_positions(position, new_position);
+        let (Some(cell), Some(neighbour)) = (self.get_cell(position), self.get_cell(new_position)) else {
+            return;
+        };
+        if cell.get_edge_type(&maze_direction) != Some(EdgeType::Wall) {
+            return;
+        }
+
+        let (edge_type, opposite_edge_type) = if cell.get_room_index() == neighbour.get_room_index() {
+            (None, None)
+        } else {
+            (Some(EdgeType::Doorway), Some(EdgeType::InverseDoorway))
+        };
+
+        let wall_light_probability = self.wall_light_probability;
+        self.get_cell_mut(position).unwrap().replace_edge(&maze_direction, edge_type, wall_light_probability, rng);
+        self.get_cell_mut(new_position).unwrap().replace_edge(&maze_direction.get_opposite_direction(), opposite_edge_type, wall_light_probability, rng);
+        if edge_type.is_some() {
+            self.add_room_link(position, new_position);
+        }
+    }
+
+    pub fn add_cell(&mut self, position: &Position, room_index: usize) {
+        let mut cell = MazeCell::new(position.x, position.y, room_index);
+        cell.toggle_render();
+        self.maze_rooms.add_cell_to_room(cell, room_index);
+    }
+
+    pub fn add_wall(&mut self, prev_position: &Position, curr_position: &Position, rng: &mut impl Rng) {
+        let maze_direction = MazeDirection::get_direction_position_from_positions(prev_position, curr_position);
+        let wall_light_probability = self.wall_light_probability;
+        let cell_leaving = self.get_cell_mut(prev_position);
+        if let Some(cell) = cell_leaving {
+            cell.add_edge(&maze_direction, Some(EdgeType::Wall) , wall_light_probability, rng);
+        }
+        let cell_entering = self.get_cell_mut(curr_position);
+        if let Some(cell) = cell_entering {
+            cell.add_edge(&maze_direction.get_opposite_direction(), Some(EdgeType::Wall), wall_light_probability, rng);
+        }
+    }
+
+    pub fn add_passage(&mut self, prev_position: &Position, curr_position: &Position, rng: &mut impl Rng) {
+        let maze_direction = MazeDirection::get_direction_position_from_positions(prev_position, curr_position);
+        let wall_light_probability = self.wall_light_probability;
+
+        let cell_leaving = self.get_cell_mut(prev_position);
+        match cell_leaving {
+            Some(cell) => {
+                cell.add_edge(&maze_direction, None, wall_light_probability, rng);
+            },
+            None => {
+                println!("No cell at position {}", format!("{:#?}", prev_position));
+            }
+        }
+        let cell_entering = self.get_cell_mut(curr_position);
+        match cell_entering {
+            Some(cell) => {
+                cell.add_edge(&maze_direction.get_opposite_direction(), None, wall_light_probability, rng);
+            },
+            None => {
+                println!("No cell at position {}", format!("{:#?}", prev_position));
+            }
+        }
+    }
+
+    pub fn add_door(&mut self, prev_position: &Position, curr_position: &Position, rng: &mut impl Rng) {
+        let maze_direction = MazeDirection::get_direction_position_from_positions(prev_position, curr_position);
+        let wall_light_probability = self.wall_light_probability;
+
+        let cell_leaving = self.get_cell_mut(prev_position);
+        match cell_leaving {
+            Some(cell) => {
+                cell.add_edge(&maze_direction, Some(EdgeType::Doorway), wall_light_probability, rng);
+            },
+            None => {
+                println!("No cell at position {}", format!("{:#?}", prev_position));
+            }
+        }
+        let cell_entering = self.get_cell_mut(curr_position);
+        match cell_entering {
+            Some(cell) => {
+                cell.add_edge(&maze_direction.get_opposite_direction(), Some(EdgeType::InverseDoorway), wall_light_probability, rng);
+            },
+            None => {
+                println!("No cell at position {}", format!("{:#?}", prev_position));
+            }
+        }
+        self.add_room_link(prev_position, curr_position);
+    }
+
+    // records the door between two cells in the RoomLinks
+    pub fn add_room_link(&mut self, position1: &Position, position2: &Position) {
+        let (Some(cell1), Some(cell2)) = (self.get_cell(position1), self.get_cell(position2)) else {
+            return;
+        };
+        let room_position1 = RoomPosition { room: cell1.get_room_index(), position: *position1 };
+        let room_position2 = RoomPosition { room: cell2.get_room_index(), position: *position2 };
+        self.room_links.add_link(room_position1, room_position2);
+    }
+
+    pub fn get_room_links(&self) -> &RoomLinks {
+        &self.room_links
+    }
+
+    // The game keeps the links in the RoomLinks resource, and doors opening and closing change them there.
+    // Taking them out of the layout leaves that as the only copy, so nothing can read a stale one
+    pub fn take_room_links(&mut self) -> RoomLinks {
+        std::mem::take(&mut self.room_links)
+    }
+
+    pub fn get_cell(&self, position: &Position) -> Option<&MazeCell> {
+        self.maze_rooms.get_cell(&position)
+    }
+
+    pub fn get_cell_mut(&mut self, position: &Position) -> Option<&mut MazeCell> {
+        self.maze_rooms.get_cell_mut(&position)
+    }
+
+    pub fn get_maze_rooms(&self) -> &MazeRooms {
+        &self.maze_rooms
+    }
+
+    pub fn get_maze_rooms_mut(&mut self) -> &mut MazeRooms {
+        &mut self.maze_rooms
+    }
+
+    // every position in the maze, row by row
+    pub fn get_positions(&self) -> Vec<Position> {
+        let mut positions = vec![];
+        for y in 0..self.size_y {
+            for x in 0..self.size_x {
+                positions.push(Position::new_from_i32(x, y));
+            }
+        }
+        positions
+    }
+
+    pub fn get_start(&self) -> Position {
+        self.start
+    }
+
+    pub fn get_exit(&self) -> Position {
+        self.exit
+    }
+
+    pub fn set_start_and_exit(&mut self, start: Position, exit: Position) {
+        self.start = start;
+        self.exit = exit;
+    }
+
+    // Shortest walk between two cells of the same room, only going through passages.  Includes both ends,
+    // None if the cells aren't in the same room or can't reach each other
+    pub fn find_path_in_room(&self, start: Position, goal: Position) -> Option<Vec<Position>> {
+        let room_index = self.get_cell(&start)?.get_room_index();
+        if self.get_cell(&goal)?.get_room_index() != room_index {
+            return None;
+        }
+
+        let get_key = |position: &Position| (position.x as i32, position.y as i32);
+        let mut previous: HashMap<(i32, i32), Position> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        previous.insert(get_key(&start), start);
+        while let Some(position) = queue.pop_front() {
+            if position == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while current != start {
+                    current = previous[&get_key(&current)];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let cell = self.get_cell(&position)?;
+            for direction_index in 0..4 {
+                let direction = MazeDirection::get_direction_from_index(direction_index);
+                if !cell.has_edge(&direction) || cell.get_edge_type(&direction).is_some() {
+                    continue;
+                }
+                let next = &position + direction.to_position_modifier();
+                if self.get_cell(&next).is_some_and(|next_cell| next_cell.get_room_index() == room_index) && !previous.contains_key(&get_key(&next)) {
+                    previous.insert(get_key(&next), position);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn contains_position(&self, position: &Position) -> bool {
+        position.x >= 0. && position.x < self.size_x as f32 && position.y >= 0. && position.y < self.size_y as f32
+    }
+
+    pub fn get_room_number_for_position(&self, position: Position) -> usize {
+        self.maze_rooms.get_room_number_for_position(position)
+    }
+}
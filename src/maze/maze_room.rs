@@ -3,9 +3,13 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 use rand::Rng;
 
-use crate::{position::Position, random::Random};
+use crate::position::Position;
 
-use super::{maze_assets::MazeAssets, maze_cell::MazeCell};
+use super::{maze_assets::MazeAssets, maze_cell::MazeCell, maze_events::RoomMerged};
+
+// The names of the settings create_all_room_settings builds, in settings index order.
+// Lets code without an asset server (like mazegen) know how many settings there are and what they are called.
+pub const ROOM_SETTINGS_NAMES: [&str; 4] = ["Basic Room", "Second Basic Room", "Bathroom", "Kitchen"];
 
 #[derive(Clone)]
 pub struct MazeRoomSettings {
@@ -23,71 +27,98 @@ pub struct RoomAssets {
     pub other_furniture: HashMap<String, Handle<Scene>>
 }
 
+impl MazeRoomSettings {
+    // builds the set of room settings a layout's settings indices refer to
+    pub fn create_all_room_settings(assets: &MazeAssets, materials: &mut Assets<StandardMaterial>) -> Vec<MazeRoomSettings> {
+        let basic_carpet = generate_material_from_image(materials, assets.carpet_1.clone());
+        let second_carpet = generate_material_from_image(materials, assets.carpet_2.clone());
+        let bathroom_tile = generate_material_from_image(materials, assets.bathroom_tile.clone());
+        let kitchen_tile = generate_material_from_image(materials, assets.kitchen_tile.clone());
+
+        let default_room_assets = RoomAssets { 
+            wall: assets.basic_wall.clone(),
+            doorway: assets.doorway.clone(),
+            door: assets.door.clone(),
+            ceiling: assets.ceiling.clone(),
+            other_furniture: HashMap::new()
+        };
+
+        let mut default_room_assets_with_wall_light = default_room_assets.clone();
+        default_room_assets_with_wall_light.other_furniture.insert(String::from("wall_light"), assets.wall_light.clone());
+        let mut default_room_assets_with_wall_light_2 = default_room_assets.clone();
+        default_room_assets_with_wall_light_2.other_furniture.insert(String::from("wall_light"), assets.wall_light_2.clone());
+
+        vec![
+            MazeRoomSettings { room_assets: default_room_assets_with_wall_light.clone(), floor: basic_carpet, name: String::from(ROOM_SETTINGS_NAMES[0]) },
+            MazeRoomSettings { room_assets: default_room_assets_with_wall_light_2.clone(), floor: second_carpet, name: String::from(ROOM_SETTINGS_NAMES[1]) },
+            MazeRoomSettings { room_assets: default_room_assets.clone(), floor: bathroom_tile, name: String::from(ROOM_SETTINGS_NAMES[2]) },
+            MazeRoomSettings { room_assets: default_room_assets.clone(), floor: kitchen_tile, name: String::from(ROOM_SETTINGS_NAMES[3]) },
+        ]
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_floor(&self) -> Handle<StandardMaterial> {
+        self.floor.clone()
+    }
+
+    pub fn get_room_assets(&self) -> RoomAssets {
+        self.room_assets.clone()
+    }
+}
+
+// A room in the layout only knows which settings it uses, the settings themselves
+// (materials, models) are attached after generation by the Maze.  The cells live in the MazeRooms grid,
+// the room just keeps the indices of the ones that belong to it
+#[derive(Clone)]
 pub struct MazeRoom {
-    settings: MazeRoomSettings,
     settings_index: usize,
-    cells: Vec<MazeCell>
+    cell_indices: Vec<usize>
 }
 
 impl MazeRoom {
-    fn new(settings: &MazeRoomSettings, settings_index: usize) -> Self {
+    fn new(settings_index: usize) -> Self {
         MazeRoom {
-            settings: settings.clone(),
             settings_index,
-            cells: vec![]
+            cell_indices: vec![]
         }
     }
 
-    pub fn get_cells(&mut self) -> &mut Vec<MazeCell> {
-        &mut self.cells
+    pub fn get_settings_index(&self) -> usize {
+        self.settings_index
     }
 
-    pub fn get_room_has_position(&self, position: Position) -> bool {
-        self.cells.iter().find(|cell| cell.get_position() == position).is_some()
+    pub fn get_cell_indices(&self) -> &Vec<usize> {
+        &self.cell_indices
     }
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct MazeRooms {
-    all_settings: Vec<MazeRoomSettings>,
-    maze_rooms: Vec<MazeRoom>
+    settings_count: usize,
+    size_x: i32,
+    size_y: i32,
+    maze_rooms: Vec<MazeRoom>,
+    // every cell of the maze row by row, so finding the cell at a position doesn't mean searching every room
+    cells: Vec<Option<MazeCell>>
 }
 
 
 impl MazeRooms {
-    pub fn new() -> Self {
-        MazeRooms::default()
-    }
-
-    pub fn initialize_maze_rooms(&mut self, assets: Res<MazeAssets>, materials: &mut ResMut<'_, Assets<StandardMaterial>>) {
-        let basic_carpet = generate_material_from_image(materials, assets.carpet_1.clone());
-        let second_carpet = generate_material_from_image(materials, assets.carpet_2.clone());
-        let bathroom_tile = generate_material_from_image(materials, assets.bathroom_tile.clone());
-        let kitchen_tile = generate_material_from_image(materials, assets.kitchen_tile.clone());
-
-        let default_room_assets = RoomAssets { 
-            wall: assets.basic_wall.clone(),
-            doorway: assets.doorway.clone(),
-            door: assets.door.clone(),
-            ceiling: assets.ceiling.clone(),
-            other_furniture: HashMap::new()
-        };
-
-        let mut default_room_assets_with_wall_light = default_room_assets.clone();
-        default_room_assets_with_wall_light.other_furniture.insert(String::from("wall_light"), assets.wall_light.clone());
-        let mut default_room_assets_with_wall_light_2 = default_room_assets.clone();
-        default_room_assets_with_wall_light_2.other_furniture.insert(String::from("wall_light"), assets.wall_light_2.clone());
-
-        self.all_settings = vec![
-            MazeRoomSettings { room_assets: default_room_assets_with_wall_light.clone(), floor: basic_carpet, name: String::from("Basic Room") },
-            MazeRoomSettings { room_assets: default_room_assets_with_wall_light_2.clone(), floor: second_carpet, name: String::from("Second Basic Room") },
-            MazeRoomSettings { room_assets: default_room_assets.clone(), floor: bathroom_tile, name: String::from("Bathroom") },
-            MazeRoomSettings { room_assets: default_room_assets.clone(), floor: kitchen_tile, name: String::from("Kitchen") },
-        ];
+    pub fn new(settings_count: usize, size_x: i32, size_y: i32) -> Self {
+        MazeRooms {
+            settings_count,
+            size_x,
+            size_y,
+            maze_rooms: vec![],
+            cells: vec![None; (size_x.max(0) * size_y.max(0)) as usize]
+        }
     }
 
-    pub fn create_room_and_return_index(&mut self, index_to_exclude: usize, rng: &mut ResMut<Random>) -> usize {
-        let settings_size = self.all_settings.len();
+    pub fn create_room_and_return_index(&mut self, index_to_exclude: usize, rng: &mut impl Rng) -> usize {
+        let settings_size = self.settings_count;
         if settings_size == 0 {
             panic!("cannot create rooms without room settings");
         }
@@ -97,37 +128,49 @@ impl MazeRooms {
             new_setting_index = (new_setting_index + 1) % settings_size
         }
 
-        let new_room = MazeRoom::new(&self.all_settings[new_setting_index], new_setting_index);
+        let new_room = MazeRoom::new(new_setting_index);
         self.maze_rooms.push(new_room);
 
         self.maze_rooms.len() - 1
     }
 
-    pub fn get_material_for_floor_by_room_index(&self, room_index: usize) -> Handle<StandardMaterial> {
-        let maze_room = &self.maze_rooms[room_index];
-        maze_room.settings.floor.clone()
+    // for rebuilding a saved layout, where the settings are already known
+    pub fn add_room(&mut self, settings_index: usize) -> usize {
+        if settings_index >= self.settings_count {
+            panic!("room settings index {} out of range", settings_index);
+        }
+        self.maze_rooms.push(MazeRoom::new(settings_index));
+        self.maze_rooms.len() - 1
     }
 
     pub fn get_settings_index_from_room_index(&self, room_index: usize) -> usize {
         self.maze_rooms[room_index].settings_index
     }
 
-    pub fn get_assets_for_room_index(&self, room_index: usize) -> RoomAssets {
-        self.maze_rooms[room_index].settings.room_assets.clone()
-    }
-
     pub fn get_room(&mut self, room_index: usize) -> &mut MazeRoom {
         &mut self.maze_rooms[room_index]
     }
 
-    pub fn get_room_by_position(&mut self, position: Position) -> Option<&MazeRoom> {
-        let maze_room = self.maze_rooms.iter().find(|maze_room| maze_room.get_room_has_position(position));
-        return maze_room;
+    pub fn get_room_by_position(&self, position: Position) -> Option<&MazeRoom> {
+        let cell = self.get_cell(&position)?;
+        self.maze_rooms.get(cell.get_room_index())
+    }
+
+    // None for positions outside of the maze
+    fn get_cell_index(&self, position: &Position) -> Option<usize> {
+        if position.x < 0. || position.y < 0. {
+            return None;
+        }
+        let (x, y) = (position.x as i32, position.y as i32);
+        if x >= self.size_x || y >= self.size_y {
+            return None;
+        }
+        Some((y * self.size_x + x) as usize)
     }
 
-    fn empty_cells_from_room(&mut self, room_index: usize) -> Vec<MazeCell> {
-        let mut emptied_cells: Vec<MazeCell> = vec![];
-        emptied_cells.append(&mut self.maze_rooms[room_index].cells);
+    fn empty_cells_from_room(&mut self, room_index: usize) -> Vec<usize> {
+        let mut emptied_cells: Vec<usize> = vec![];
+        emptied_cells.append(&mut self.maze_rooms[room_index].cell_indices);
 
         emptied_cells
     }
@@ -137,37 +180,61 @@ impl MazeRooms {
     }
 
     pub fn add_cell_to_room(&mut self, cell: MazeCell, room_index: usize) {
-        self.maze_rooms[room_index].cells.push(cell);
+        let cell_index = self.get_cell_index(&cell.get_position()).expect("Tried to add a cell outside of the maze");
+        self.cells[cell_index] = Some(cell);
+        self.maze_rooms[room_index].cell_indices.push(cell_index);
     }
 
-    pub fn merge_rooms(&mut self, room_to_keep: usize, room_to_merge: usize) {
+    // Joins two rooms into one.  The lower id always survives, so a region that merges over and over keeps the
+    // oldest id it had, and every cell that moves takes that id.  The emptied room stays where it is until
+    // compact_rooms so no other room's id changes halfway through generating
+    pub fn merge_rooms(&mut self, room_a: usize, room_b: usize) -> Option<RoomMerged> {
+        if room_a == room_b {
+            return None;
+        }
+        let (room_to_keep, room_to_merge) = (room_a.min(room_b), room_a.max(room_b));
+
         let mut cells = self.empty_cells_from_room(room_to_merge);
-        let kept_room = &mut self.get_room(room_to_keep).cells;
+        for cell_index in &cells {
+            if let Some(cell) = self.cells[*cell_index].as_mut() {
+                cell.set_room_index(room_to_keep);
+            }
+        }
+        let kept_room = &mut self.get_room(room_to_keep).cell_indices;
         kept_room.append(&mut cells);
+
+        Some(RoomMerged { kept_room: room_to_keep, merged_room: room_to_merge })
     }
 
-    pub fn get_cell_mut(&mut self, position: &Position) -> Option<&mut MazeCell> {
-        let mut cell: Option<&mut MazeCell> = None;
-        for room in self.maze_rooms.iter_mut() {
-            let possible_cell = room.cells.iter_mut().find(|cell| cell.get_position() == *position);
-            if possible_cell.is_some() {
-                cell = possible_cell;
-                break;
+    // Drops the rooms that merging left empty and renumbers the rest, and their cells, to close the gaps
+    pub fn compact_rooms(&mut self) {
+        let mut new_room_indices = vec![0; self.maze_rooms.len()];
+        let mut next_room_index = 0;
+        for (room_index, room) in self.maze_rooms.iter().enumerate() {
+            new_room_indices[room_index] = next_room_index;
+            if !room.cell_indices.is_empty() {
+                next_room_index += 1;
             }
         }
-        cell
+
+        self.maze_rooms.retain(|room| !room.cell_indices.is_empty());
+        for cell in self.cells.iter_mut().flatten() {
+            cell.set_room_index(new_room_indices[cell.get_room_index()]);
+        }
+    }
+
+    pub fn get_room_cells(&self, room_index: usize) -> impl Iterator<Item = &MazeCell> {
+        self.maze_rooms[room_index].cell_indices.iter().filter_map(|cell_index| self.cells[*cell_index].as_ref())
+    }
+
+    pub fn get_cell_mut(&mut self, position: &Position) -> Option<&mut MazeCell> {
+        let cell_index = self.get_cell_index(position)?;
+        self.cells[cell_index].as_mut()
     }
 
     pub fn get_cell(&self, position: &Position) -> Option<&MazeCell> {
-        let mut cell: Option<&MazeCell> = None;
-        for room in self.maze_rooms.iter() {
-            let possible_cell = room.cells.iter().find(|cell| cell.get_position() == *position);
-            if possible_cell.is_some() {
-                cell = possible_cell;
-                break;
-            }
-        }
-        cell
+        let cell_index = self.get_cell_index(position)?;
+        self.cells[cell_index].as_ref()
     }
 
     // should move this to maze ??
@@ -176,27 +243,31 @@ impl MazeRooms {
         commands: &mut Commands,
         meshes: &mut ResMut<Assets<Mesh>>,
         floors: Entity,
-        room_index: usize
+        room_index: usize,
+        settings: &MazeRoomSettings,
+        maze_scale: f32
     ) {
         // get necessary parts
-        let floor_material = self.get_material_for_floor_by_room_index(room_index).clone();
-        let room_assets = self.get_assets_for_room_index(room_index).clone();
+        let floor_material = settings.get_floor();
+        let room_assets = settings.get_room_assets();
         // get the cells for the room
-        let cells = self.get_room(room_index).get_cells();
+        let cell_indices = &self.maze_rooms[room_index].cell_indices;
         // iterate over them
-        cells.iter_mut().for_each(|cell| {
+        for cell_index in cell_indices {
         // render each cell
-            cell.render_cell(commands, meshes, floor_material.clone(), room_assets.clone(), floors);
-        })
+            if let Some(cell) = self.cells[*cell_index].as_mut() {
+                cell.render_cell(commands, meshes, floor_material.clone(), room_assets.clone(), floors, maze_scale);
+            }
+        }
     }
 
     pub fn get_room_number_for_position(&self, position: Position) -> usize {
-        let room_number: usize = self.maze_rooms.iter().position(|room| room.get_room_has_position(position)).expect("get_room_number_for_position: Position not in room");
+        let room_number: usize = self.get_cell(&position).expect("get_room_number_for_position: Position not in room").get_room_index();
         room_number
     }
 }
 
-fn generate_material_from_image(materials: &mut ResMut<'_, Assets<StandardMaterial>>, image: Handle<Image>) -> Handle<StandardMaterial> {
+fn generate_material_from_image(materials: &mut Assets<StandardMaterial>, image: Handle<Image>) -> Handle<StandardMaterial> {
     materials.add(StandardMaterial {
         base_color_texture: Some(image),
         alpha_mode: AlphaMode::Blend,
@@ -207,4 +278,100 @@ fn generate_material_from_image(materials: &mut ResMut<'_, Assets<StandardMateri
 
 // or should maze cells be a position?
 // is it better for the maze to hold all the cells, and the rooms to then have the positions for the cells?
-// or should the rooms have the cells, and the maze then has to query the rooms, and the rooms then would have the cells
\ No newline at end of file
+// or should the rooms have the cells, and the maze then has to query the rooms, and the rooms then would have the cells
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::maze::generators::growing_tree::CellSelection;
+    use crate::maze::generators::maze_generator::MazeAlgorithm;
+    use crate::maze::maze_cell_edge::EdgeType;
+    use crate::maze::maze_config::MazeConfig;
+    use crate::maze::maze_direction::MazeDirection;
+    use crate::maze::maze_layout::MazeLayout;
+
+    use super::*;
+
+    const ALGORITHMS: [MazeAlgorithm; 7] = [
+        MazeAlgorithm::GrowingTree(CellSelection::Newest),
+        MazeAlgorithm::GrowingTree(CellSelection::Random),
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::RecursiveDivision
+    ];
+
+    fn assert_rooms_own_their_cells(layout: &MazeLayout) {
+        let maze_rooms = layout.get_maze_rooms();
+        let mut owned_cells = 0;
+        for room_index in 0..maze_rooms.get_room_count() {
+            let cells: Vec<&MazeCell> = maze_rooms.get_room_cells(room_index).collect();
+            assert!(!cells.is_empty(), "room {} has no cells", room_index);
+            for cell in cells {
+                assert_eq!(cell.get_room_index(), room_index, "cell {:?} is owned by room {} but thinks it's in room {}", cell.get_position(), room_index, cell.get_room_index());
+                owned_cells += 1;
+            }
+        }
+        assert_eq!(owned_cells, (layout.size_x * layout.size_y) as usize);
+    }
+
+    #[test]
+    fn merging_keeps_the_lower_room_and_compacting_drops_the_empty_one() {
+        let mut maze_rooms = MazeRooms::new(2, 3, 1);
+        for x in 0..3 {
+            let room_index = maze_rooms.add_room(x % 2);
+            maze_rooms.add_cell_to_room(MazeCell::new(x as f32, 0., room_index), room_index);
+        }
+
+        assert_eq!(maze_rooms.merge_rooms(2, 0), Some(RoomMerged { kept_room: 0, merged_room: 2 }));
+        assert_eq!(maze_rooms.merge_rooms(0, 0), None);
+        assert_eq!(maze_rooms.get_room_number_for_position(Position::new(2., 0.)), 0);
+
+        maze_rooms.compact_rooms();
+        assert_eq!(maze_rooms.get_room_count(), 2);
+        assert_eq!(maze_rooms.get_room_number_for_position(Position::new(0., 0.)), 0);
+        assert_eq!(maze_rooms.get_room_number_for_position(Position::new(1., 0.)), 1);
+        assert_eq!(maze_rooms.get_room_number_for_position(Position::new(2., 0.)), 0);
+        for room_index in 0..2 {
+            assert!(maze_rooms.get_room_cells(room_index).all(|cell| cell.get_room_index() == room_index));
+        }
+    }
+
+    #[test]
+    fn generated_rooms_own_their_cells() {
+        for algorithm in ALGORITHMS {
+            for seed in 0..5 {
+                let config = MazeConfig { maze_x: 12, maze_y: 9, algorithm, ..Default::default() };
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let mut layout = MazeLayout::generate(&config, 4, &config.algorithm, &mut rng);
+                assert_rooms_own_their_cells(&layout);
+
+                config.braid.braid(&mut layout, &mut rng);
+                assert_rooms_own_their_cells(&layout);
+            }
+        }
+    }
+
+    #[test]
+    fn passages_stay_inside_rooms_and_doors_join_different_ones() {
+        for algorithm in ALGORITHMS {
+            let config = MazeConfig { maze_x: 12, maze_y: 9, algorithm, ..Default::default() };
+            let mut rng = ChaCha8Rng::seed_from_u64(7);
+            let layout = MazeLayout::generate(&config, 4, &config.algorithm, &mut rng);
+            for position in layout.get_positions() {
+                let cell = layout.get_cell(&position).unwrap();
+                for direction_index in 0..4 {
+                    let direction = MazeDirection::get_direction_from_index(direction_index);
+                    let Some(neighbour) = layout.get_cell(&(&position + direction.to_position_modifier())) else { continue };
+                    match cell.get_edge_type(&direction) {
+                        None => assert_eq!(cell.get_room_index(), neighbour.get_room_index(), "{:?} has a passage {:?} into another room", position, direction),
+                        Some(EdgeType::Doorway) | Some(EdgeType::InverseDoorway) => assert_ne!(cell.get_room_index(), neighbour.get_room_index(), "{:?} has a door {:?} inside its own room", position, direction),
+                        Some(EdgeType::Wall) => {}
+                    }
+                }
+            }
+        }
+    }
+}
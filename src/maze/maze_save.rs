@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::position::Position;
+use crate::random::MazeSeed;
+use super::maze_cell_edge::{EdgeType, MazeCellEdge};
+use super::maze_direction::MazeDirection;
+use super::maze_export::get_edge_name;
+use super::maze_layout::MazeLayout;
+use super::paintings::Painting;
+
+// Bump this whenever the saved format changes in a way older files can't be read as
+pub const MAZE_SAVE_VERSION: u32 = 1;
+pub const DEFAULT_SAVE_PATH: &str = "saved_maze.ron";
+
+/**
+ * A MazeLayout as it is written to disk, either RON or JSON depending on the file extension.
+ * Rooms are saved by the name of their settings rather than the index so the file still loads if the
+ * settings get reordered.  Each cell lists all four of its edges, where an edge_type of None is a passage.
+ * Door orientation is the Doorway/InverseDoorway pair plus the edge's direction.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MazeSave {
+    pub version: u32,
+    pub seed: Option<String>,
+    pub size_x: i32,
+    pub size_y: i32,
+    // older saves without these use the default corners
+    #[serde(default)]
+    pub start: Option<[i32; 2]>,
+    #[serde(default)]
+    pub exit: Option<[i32; 2]>,
+    pub rooms: Vec<SavedRoom>,
+    pub cells: Vec<SavedCell>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRoom {
+    pub settings: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedCell {
+    pub x: i32,
+    pub y: i32,
+    pub room: usize,
+    pub edges: Vec<SavedEdge>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedEdge {
+    pub direction: MazeDirection,
+    pub edge_type: Option<EdgeType>,
+    #[serde(default)]
+    pub painting: Option<Painting>,
+    #[serde(default)]
+    pub wall_furniture: Vec<String>
+}
+
+// A layout read from --load or --import, waiting for GameState::Initialize to turn it into the Maze
+#[derive(Resource)]
+pub struct LoadedMazeLayout(pub MazeLayout);
+
+impl MazeSave {
+    pub fn from_layout(layout: &MazeLayout, room_names: &[&str], seed: Option<MazeSeed>) -> Self {
+        let maze_rooms = layout.get_maze_rooms();
+        let rooms = (0..maze_rooms.get_room_count()).map(|room| {
+            let settings_index = maze_rooms.get_settings_index_from_room_index(room);
+            SavedRoom { settings: String::from(room_names[settings_index]) }
+        }).collect();
+
+        let cells = layout.get_positions().iter().filter_map(|position| {
+            let cell = layout.get_cell(position)?;
+            let edges = cell.get_edges().map(|(direction, edge)| SavedEdge {
+                direction: *direction,
+                edge_type: edge.as_ref().map(|edge| edge.get_edge_type()),
+                painting: edge.as_ref().and_then(|edge| edge.get_painting().cloned()),
+                wall_furniture: edge.as_ref().map(|edge| edge.get_wall_furniture().clone()).unwrap_or_default()
+            }).collect();
+            Some(SavedCell { x: position.x as i32, y: position.y as i32, room: cell.get_room_index(), edges })
+        }).collect();
+
+        MazeSave {
+            version: MAZE_SAVE_VERSION,
+            seed: seed.map(|seed| seed.to_code()),
+            size_x: layout.size_x,
+            size_y: layout.size_y,
+            start: Some([layout.get_start().x as i32, layout.get_start().y as i32]),
+            exit: Some([layout.get_exit().x as i32, layout.get_exit().y as i32]),
+            rooms,
+            cells
+        }
+    }
+
+    pub fn to_layout(&self, room_names: &[&str]) -> Result<MazeLayout, String> {
+        if self.version != MAZE_SAVE_VERSION {
+            return Err(format!("Saved maze is version {}, this build reads version {}", self.version, MAZE_SAVE_VERSION));
+        }
+        if self.size_x < 1 || self.size_y < 1 {
+            return Err(format!("Saved maze must be at least 1x1, got {}x{}", self.size_x, self.size_y));
+        }
+        if self.cells.len() != (self.size_x * self.size_y) as usize {
+            return Err(format!("Saved maze is {}x{} but has {} cells", self.size_x, self.size_y, self.cells.len()));
+        }
+
+        let mut layout = MazeLayout::with_size(self.size_x, self.size_y, room_names.len());
+        for room in &self.rooms {
+            let settings_index = room_names.iter().position(|name| *name == room.settings)
+                .ok_or(format!("Unknown room settings in saved maze: {}", room.settings))?;
+            layout.add_room(settings_index);
+        }
+
+        for saved_cell in &self.cells {
+            let position = Position::new_from_i32(saved_cell.x, saved_cell.y);
+            if !layout.contains_position(&position) {
+                return Err(format!("Saved cell ({}, {}) is outside the maze", saved_cell.x, saved_cell.y));
+            }
+            if layout.get_cell(&position).is_some() {
+                return Err(format!("Saved cell ({}, {}) appears twice", saved_cell.x, saved_cell.y));
+            }
+            if saved_cell.room >= self.rooms.len() {
+                return Err(format!("Saved cell ({}, {}) is in room {} but there are only {} rooms", saved_cell.x, saved_cell.y, saved_cell.room, self.rooms.len()));
+            }
+
+            layout.add_cell(&position, saved_cell.room);
+            let cell = layout.get_cell_mut(&position).unwrap();
+            for saved_edge in &saved_cell.edges {
+                if cell.has_edge(&saved_edge.direction) {
+                    return Err(format!("Saved cell ({}, {}) has two {:?} edges", saved_cell.x, saved_cell.y, saved_edge.direction));
+                }
+                let edge = saved_edge.edge_type.map(|edge_type| {
+                    MazeCellEdge::from_saved(&saved_edge.direction, edge_type, saved_edge.painting.clone(), saved_edge.wall_furniture.clone())
+                });
+                cell.add_saved_edge(&saved_edge.direction, edge);
+            }
+            if !cell.is_edge_complete() {
+                return Err(format!("Saved cell ({}, {}) is missing edges", saved_cell.x, saved_cell.y));
+            }
+        }
+
+        // both sides of an edge are saved separately, so make sure they agree: a wall both ways, open both ways,
+        // or a Doorway with its InverseDoorway on the other side
+        for saved_cell in &self.cells {
+            let position = Position::new_from_i32(saved_cell.x, saved_cell.y);
+            for saved_edge in &saved_cell.edges {
+                let neighbour_position = &position + saved_edge.direction.to_position_modifier();
+                let Some(neighbour) = layout.get_cell(&neighbour_position) else { continue };
+                let other_side = neighbour.get_edge_type(&saved_edge.direction.get_opposite_direction());
+                let expected = match saved_edge.edge_type {
+                    Some(EdgeType::Doorway) => Some(EdgeType::InverseDoorway),
+                    Some(EdgeType::InverseDoorway) => Some(EdgeType::Doorway),
+                    edge_type => edge_type
+                };
+                if other_side != expected {
+                    return Err(format!("Saved cell ({}, {}) has '{}' on its {:?} edge but ({}, {}) has '{}' on the other side",
+                        saved_cell.x, saved_cell.y, get_edge_name(saved_edge.edge_type), saved_edge.direction,
+                        neighbour_position.x, neighbour_position.y, get_edge_name(other_side)));
+                }
+            }
+        }
+
+        // the doors only exist as edges in the file, so the room links are rebuilt from the Doorway sides
+        for saved_cell in &self.cells {
+            let position = Position::new_from_i32(saved_cell.x, saved_cell.y);
+            for saved_edge in saved_cell.edges.iter().filter(|saved_edge| saved_edge.edge_type == Some(EdgeType::Doorway)) {
+                layout.add_room_link(&position, &(&position + saved_edge.direction.to_position_modifier()));
+            }
+        }
+
+        let start = self.start.map_or(layout.get_start(), |[x, y]| Position::new_from_i32(x, y));
+        let exit = self.exit.map_or(layout.get_exit(), |[x, y]| Position::new_from_i32(x, y));
+        if !layout.contains_position(&start) || !layout.contains_position(&exit) {
+            return Err(String::from("Saved maze has its start or exit outside the maze"));
+        }
+        layout.set_start_and_exit(start, exit);
+
+        Ok(layout)
+    }
+
+    pub fn get_seed(&self) -> Result<Option<MazeSeed>, String> {
+        self.seed.as_ref().map(|code| code.parse()).transpose()
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(self).map_err(|error| error.to_string())?
+        } else {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|error| error.to_string())?
+        };
+        fs::write(path, contents).map_err(|error| format!("Could not write saved maze {}: {}", path, error))
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Could not read saved maze {}: {}", path, error))?;
+        if is_json(path) {
+            serde_json::from_str(&contents).map_err(|error| format!("Could not parse saved maze {}: {}", path, error))
+        } else {
+            ron::from_str(&contents).map_err(|error| format!("Could not parse saved maze {}: {}", path, error))
+        }
+    }
+
+    // --load <path>, if it was given
+    pub fn load_from_args(args: &[String]) -> Result<Option<Self>, String> {
+        match args.iter().position(|arg| arg == "--load") {
+            Some(index) => {
+                let path = args.get(index + 1).ok_or("--load needs a path")?;
+                MazeSave::load_from_file(path).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+}
+
+fn is_json(path: &str) -> bool {
+    Path::new(path).extension().map_or(false, |extension| extension == "json")
+}
@@ -6,4 +6,12 @@ pub mod paintings;
 pub mod maze_door;
 pub mod maze_assets;
 pub mod maze_room;
-pub mod room_links;
\ No newline at end of file
+pub mod maze_layout;
+pub mod maze_config;
+pub mod maze_export;
+pub mod maze_save;
+pub mod maze_import;
+pub mod maze_events;
+pub mod generators;
+pub mod room_links;
+pub mod line_of_sight;
\ No newline at end of file
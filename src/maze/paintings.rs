@@ -1,10 +1,11 @@
 use bevy::prelude::*;
 
-use crate::{consts, random::Random};
+use crate::consts;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 #[repr(u32)]
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PaintingType {
     Square,
     LongHorizontal,
@@ -25,15 +26,15 @@ impl PaintingType {
         unsafe { ::std::mem::transmute(index) }
     }
 
-    pub fn get_random_painting_type(rand: &mut ResMut<Random>) -> PaintingType {
-        let index = rand.gen_range(0..3);
+    pub fn get_random_painting_type(rng: &mut impl Rng) -> PaintingType {
+        let index = rng.gen_range(0..3);
         PaintingType::get_painting_type_from_index(index)
     }
 
 }
 
 #[repr(u32)]
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PaintingColor {
     Red,
     Yellow,
@@ -54,13 +55,13 @@ impl PaintingColor {
         unsafe { ::std::mem::transmute(index) }
     }
 
-    pub fn get_random_painting_color(rand: &mut ResMut<Random>) -> PaintingColor {
-        let index = rand.gen_range(0..3);
+    pub fn get_random_painting_color(rng: &mut impl Rng) -> PaintingColor {
+        let index = rng.gen_range(0..3);
         PaintingColor::get_painting_color_from_index(index)
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Painting {
     painting_type: PaintingType,
     painting_color: PaintingColor,
@@ -78,17 +79,17 @@ impl Painting {
         }
     }
 
-    pub fn generate_random_painting(rand: &mut ResMut<Random>) -> Option<Painting> {
-        let random: f32 = rand.gen_range(0. .. 1.);
+    pub fn generate_random_painting(painting_probability: f32, rng: &mut impl Rng) -> Option<Painting> {
+        let random: f32 = rng.gen_range(0. .. 1.);
 
-        if random > consts::PROBABILITY_PAINTING {
+        if random > painting_probability {
             return None
         }
 
         let painting = Painting {
-            painting_type: PaintingType::get_random_painting_type(rand),
-            painting_color: PaintingColor::get_random_painting_color(rand),
-            is_north_west: rand.gen_bool(0.5)
+            painting_type: PaintingType::get_random_painting_type(rng),
+            painting_color: PaintingColor::get_random_painting_color(rng),
+            is_north_west: rng.gen_bool(0.5)
         };
 
         Some(painting)
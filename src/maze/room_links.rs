@@ -1,16 +1,17 @@
-use std::ops::Index;
-
 use bevy::prelude::Resource;
 
 use crate::position::Position;
 
-use super::maze_direction::MazeDirection;
+use super::maze_room::MazeRooms;
 
-struct RoomPosition {
-    room: usize,
-    position: Position
+// One side of a link: the room, and the cell in that room the door is in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoomPosition {
+    pub room: usize,
+    pub position: Position
 }
 
+#[derive(Debug, Clone)]
 pub struct RoomLink {
     room_position1: RoomPosition,
     room_position2: RoomPosition,
@@ -22,20 +23,69 @@ impl RoomLink {
         self.room_position1.room == room_number || self.room_position2.room == room_number
     }
 
-    fn is_open(&self) -> bool {
+    pub fn is_open(&self) -> bool {
         self.is_open
     }
 
-    fn open_link(&mut self) {
+    pub fn open_link(&mut self) {
         self.is_open = true;
     }
+
+    pub fn close_link(&mut self) {
+        self.is_open = false;
+    }
+
+    pub fn get_room_positions(&self) -> (RoomPosition, RoomPosition) {
+        (self.room_position1, self.room_position2)
+    }
+
+    // the side of the link in the given room
+    pub fn get_room_position(&self, room_number: usize) -> Option<RoomPosition> {
+        if self.room_position1.room == room_number {
+            Some(self.room_position1)
+        } else if self.room_position2.room == room_number {
+            Some(self.room_position2)
+        } else {
+            None
+        }
+    }
+
+    // the side of the link that isn't in the given room
+    pub fn get_other_room_position(&self, room_number: usize) -> Option<RoomPosition> {
+        if self.room_position1.room == room_number {
+            Some(self.room_position2)
+        } else if self.room_position2.room == room_number {
+            Some(self.room_position1)
+        } else {
+            None
+        }
+    }
+
+    fn has_positions(&self, position1: &Position, position2: &Position) -> bool {
+        (self.room_position1.position == *position1 && self.room_position2.position == *position2)
+            || (self.room_position1.position == *position2 && self.room_position2.position == *position1)
+    }
 }
 
-#[derive(Resource)]
+/**
+ * Which rooms connect to which, one link per door.  The layout adds a link every time it adds a door,
+ * so this is the room level graph of the maze, and find_room_path searches it.  Once the game starts it's a
+ * resource alongside the Maze, and door_open_system opens and closes the links as the doors swing.
+ */
+#[derive(Resource, Default, Clone)]
 pub struct RoomLinks {
     pub room_links: Vec<RoomLink>
 }
 
+// A step in the room path search: having gone through a link into a room
+struct RoomPathNode {
+    link_index: usize,
+    room: usize,
+    g: usize,
+    h: usize,
+    previous: Option<usize>
+}
+
 impl RoomLinks {
     pub fn new () -> Self {
         RoomLinks {
@@ -43,8 +93,38 @@ impl RoomLinks {
         }
     }
 
-    pub fn add_link(position: Position, direction: MazeDirection) {
-        
+    // doors start closed
+    pub fn add_link(&mut self, room_position1: RoomPosition, room_position2: RoomPosition) {
+        self.room_links.push(RoomLink { room_position1, room_position2, is_open: false });
+    }
+
+    pub fn open_link_between(&mut self, position1: &Position, position2: &Position) {
+        if let Some(room_link) = self.room_links.iter_mut().find(|room_link| room_link.has_positions(position1, position2)) {
+            room_link.open_link();
+        }
+    }
+
+    pub fn close_link_between(&mut self, position1: &Position, position2: &Position) {
+        if let Some(room_link) = self.room_links.iter_mut().find(|room_link| room_link.has_positions(position1, position2)) {
+            room_link.close_link();
+        }
+    }
+
+    // false if the door between the cells is closed, or there is no door there at all
+    pub fn is_open_between(&self, position1: &Position, position2: &Position) -> bool {
+        self.room_links.iter().any(|room_link| room_link.has_positions(position1, position2) && room_link.is_open())
+    }
+
+    // Rooms get merged and renumbered while a maze is generated, the positions don't move though,
+    // so this looks the room numbers back up from them
+    pub fn refresh_rooms(&mut self, maze_rooms: &MazeRooms) {
+        for room_link in self.room_links.iter_mut() {
+            for room_position in [&mut room_link.room_position1, &mut room_link.room_position2] {
+                if let Some(cell) = maze_rooms.get_cell(&room_position.position) {
+                    room_position.room = cell.get_room_index();
+                }
+            }
+        }
     }
 
     pub fn get_room_links(&self, room_number: usize) -> Vec<&RoomLink> {
@@ -57,13 +137,73 @@ impl RoomLinks {
         // to room, we can do the pathfinding as a series of room links until the nav agent is in the room with the nav target
         // so we need to see if we can find a path from starting_room to ending_room
         // and the the output will be the room numbers for each room, and we can go pairwise through to find the path as the agent moves from room to room
-        let mut open = vec![starting_room];
+        if starting_room == ending_room {
+            return vec![starting_room];
+        }
+
+        // the doors into the ending room are what the estimate aims for
+        let goals: Vec<Position> = self.get_room_links(ending_room).iter()
+            .filter_map(|room_link| room_link.get_room_position(ending_room))
+            .map(|room_position| room_position.position)
+            .collect();
+        let estimate = |room_link: &RoomLink| goals.iter().map(|goal| get_estimated_distance(room_link, *goal)).min().unwrap_or(0);
+
+        // g is how far has been walked, counting the distance across each room between its doors plus one per door
+        let mut nodes: Vec<RoomPathNode> = vec![];
+        let mut open: Vec<usize> = vec![];
         let mut closed: Vec<(usize, usize)> = vec![];
+        for (link_index, room_link) in self.room_links.iter().enumerate() {
+            if let Some(next) = room_link.get_other_room_position(starting_room) {
+                nodes.push(RoomPathNode { link_index, room: next.room, g: 1, h: estimate(room_link), previous: None });
+                open.push(nodes.len() - 1);
+            }
+        }
+
         let mut path: Vec<usize> = vec![];
         let mut path_found = false;
 
         while open.len() > 0 && path_found == false {
-            
+            let (open_index, &node_index) = open.iter().enumerate()
+                .min_by_key(|(_, node_index)| nodes[**node_index].g + nodes[**node_index].h)
+                .unwrap();
+            open.swap_remove(open_index);
+
+            let (link_index, room, g) = (nodes[node_index].link_index, nodes[node_index].room, nodes[node_index].g);
+            if closed.contains(&(link_index, room)) {
+                continue;
+            }
+            closed.push((link_index, room));
+
+            if room == ending_room {
+                path_found = true;
+                let mut current = Some(node_index);
+                while let Some(index) = current {
+                    path.push(nodes[index].room);
+                    current = nodes[index].previous;
+                }
+                path.push(starting_room);
+                path.reverse();
+                break;
+            }
+
+            let entered_at = self.room_links[link_index].get_room_position(room).unwrap().position;
+            for (next_link_index, next_link) in self.room_links.iter().enumerate() {
+                if next_link_index == link_index {
+                    continue;
+                }
+                let (Some(exit), Some(next)) = (next_link.get_room_position(room), next_link.get_other_room_position(room)) else { continue };
+                if closed.contains(&(next_link_index, next.room)) {
+                    continue;
+                }
+                nodes.push(RoomPathNode {
+                    link_index: next_link_index,
+                    room: next.room,
+                    g: g + entered_at.get_distance_to_position(exit.position) + 1,
+                    h: estimate(next_link),
+                    previous: Some(node_index)
+                });
+                open.push(nodes.len() - 1);
+            }
         }
 
         // if path ends up empty, then we know there was no path between the rooms
@@ -71,7 +211,46 @@ impl RoomLinks {
     }
 }
 
-fn get_estimated_distance(start: RoomLink, goal: Position) -> usize {
-    // for the room link, we have two positions
+fn get_estimated_distance(start: &RoomLink, goal: Position) -> usize {
+    // for the room link, we have two positions, either could be the closer one
     goal.get_distance_to_position(start.room_position1.position)
+        .min(goal.get_distance_to_position(start.room_position2.position))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::maze_import::test_layouts::{import, FIVE_ROOMS};
+    use crate::maze::maze_layout::MazeLayout;
+
+    use super::*;
+
+    fn room_at(layout: &MazeLayout, x: i32, y: i32) -> usize {
+        layout.get_room_number_for_position(Position::new_from_i32(x, y))
+    }
+
+    #[test]
+    fn finds_the_shortest_way_between_rooms() {
+        let layout = import(FIVE_ROOMS);
+        let room_links = layout.get_room_links();
+        assert_eq!(room_links.room_links.len(), 5);
+
+        let (a, b, c, d, e) = (room_at(&layout, 0, 0), room_at(&layout, 2, 0), room_at(&layout, 4, 0), room_at(&layout, 2, 1), room_at(&layout, 4, 1));
+        assert_eq!(room_links.find_room_path(a, e), vec![a, d, e]);
+        assert_eq!(room_links.find_room_path(e, a), vec![e, d, a]);
+        assert_eq!(room_links.find_room_path(b, c), vec![b, c]);
+        assert_eq!(room_links.find_room_path(a, a), vec![a]);
+    }
+
+    #[test]
+    fn walled_off_rooms_have_no_path() {
+        let layout = import("
++-+-+
+|S|b|
++ +-+
+|a E|
++-+-+
+");
+        let (a, b) = (room_at(&layout, 0, 0), room_at(&layout, 1, 0));
+        assert_eq!(layout.get_room_links().find_room_path(a, b), Vec::<usize>::new());
+    }
 }
@@ -1,3 +1,9 @@
 pub mod monster;
 pub mod monster_assets;
-pub mod monster_events;
\ No newline at end of file
+pub mod monster_events;
+pub mod monster_ai;
+pub mod monster_perception;
+pub mod monster_catalog;
+pub mod monster_spawner;
+pub mod monster_animation;
+pub mod monster_route;
\ No newline at end of file
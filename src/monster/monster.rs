@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
-use crate::{character::character::{CharacterBundle, Speed}, consts, game_states::GameState, maze::maze::Maze, physics::velocity::Velocity, player::player::LogicalPlayer, position::Position};
+use crate::{character::character::{CharacterBundle, Speed}, flow_field::FlowField, game_states::GameState, maze::{line_of_sight::has_line_of_sight, maze::Maze, maze_config::MazeConfig, maze_events::DoorStateChanged, room_links::RoomLinks}, noise::NoiseEvent, pathfinding::{EdgeCosts, PathfindingOptions}, physics::{collider::move_colliders, interpolation::{interpolate_transforms, InterpolatedTransform}, velocity::Velocity}, player::{player::{Controller, LogicalPlayer, PlayerLight}, player_events::PlayerCellChangeEvent}, position::Position, random::Random};
 
-use super::{monster_assets::MonsterAssets, monster_events::MonsterReachedPlayer};
+use super::{monster_ai::{MonsterAi, MonsterState}, monster_animation::{attach_monster_animation_players, blend_monster_animations, disable_skinned_mesh_culling, MonsterAnimations}, monster_assets::MonsterAssets, monster_catalog::MonsterCatalog, monster_events::{MonsterReachedPlayer, PlayerLost, PlayerSeen}, monster_perception::{MonsterHearing, MonsterPerception}, monster_spawner::choose_spawn_cells};
 
 // probably want a speed, position, velocity, maybe some way to track the player, maybe a pathfinding goal?
 // Most of those are probably components?  Pathfinding goal should be a component for sure
@@ -25,73 +25,256 @@ pub struct MonsterPlugin;
 
 impl Plugin for MonsterPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Initialize), spawn_demon)
-            .add_systems(Update, (pathfind_towards_player, move_agents_towards_goals).chain().run_if(in_state(GameState::InGame)))
-            .add_event::<MonsterReachedPlayer>();
+        app.insert_resource(FlowField::new(get_monster_pathfinding_options()))
+            .add_systems(OnEnter(GameState::InGame), spawn_monsters)
+            // steering reads where physics has things, so it runs on the same ticks, just before they're moved
+            .add_systems(FixedUpdate, (
+                update_flow_field,
+                update_monster_perception,
+                hear_noises,
+                update_monster_ai,
+                pathfind_towards_player,
+                follow_monster_paths,
+                move_agents_towards_goals
+            ).chain().before(move_colliders).run_if(in_state(GameState::InGame)))
+            .add_systems(Update, draw_monster_ai_gizmos.run_if(in_state(GameState::InGame)))
+            .add_systems(Update, (attach_monster_animation_players, blend_monster_animations.after(interpolate_transforms)).run_if(in_state(GameState::InGame)))
+            .add_systems(Update, disable_skinned_mesh_culling.run_if(in_state(GameState::InGame)))
+            .add_event::<MonsterReachedPlayer>()
+            .add_event::<PlayerSeen>()
+            .add_event::<PlayerLost>()
+            .register_type::<MonsterAi>()
+            .register_type::<MonsterPerception>()
+            .register_type::<MonsterHearing>();
     }
 }
 
-// lets spawn the monster in the center for now
-fn spawn_demon(mut commands: Commands, assets: Res<MonsterAssets>) {
-    let demon_position = Position::new((consts::MAZE_X / 2) as f32, (consts::MAZE_Y / 2) as f32);
-    commands.spawn((
-        MonsterBundle {
-            character_bundle: CharacterBundle {
-                velocity: Velocity::new(0., 0.),
-                position: demon_position,
-                speed: Speed(5.0)
-            },
-            scene_bundle: SceneBundle {
-                scene: assets.demon_model.clone(),
-                transform: Transform::from_translation(demon_position.to_vec3_by_scale(consts::MAZE_SCALE)).with_scale(Vec3::splat(2.2)),
-                ..default()
+// the maze has to exist to know where monsters can go, so they are spawned on entering the game rather than on Initialize
+fn spawn_monsters(
+    mut commands: Commands,
+    assets: Res<MonsterAssets>,
+    catalog: Res<MonsterCatalog>,
+    maze: Res<Maze>,
+    room_links: Res<RoomLinks>,
+    mut rng: ResMut<Random>,
+    config: Res<MazeConfig>
+) {
+    let spawn_cells = choose_spawn_cells(maze.get_layout(), &room_links, catalog.spawn_count, &catalog.spawn_distance, &mut rng.0);
+    if spawn_cells.len() < catalog.spawn_count {
+        warn!("Only room for {} of {} monsters in this maze", spawn_cells.len(), catalog.spawn_count);
+    }
+
+    for spawn_cell in spawn_cells {
+        let Some(monster_type) = catalog.choose_monster_type(&mut rng.0) else { continue };
+        commands.spawn((
+            MonsterBundle {
+                character_bundle: CharacterBundle {
+                    velocity: Velocity::new(0., 0.),
+                    position: spawn_cell,
+                    speed: Speed(monster_type.speed)
+                },
+                scene_bundle: SceneBundle {
+                    scene: assets.get_model(&monster_type.model),
+                    transform: Transform::from_translation(spawn_cell.to_vec3_by_scale(config.maze_scale)).with_scale(Vec3::splat(monster_type.scale)),
+                    ..default()
+                },
             },
-        },
-        Name::new(String::from("Demon")),
-        NavigateToPlayer,
-        PathfindingGoal { goal: None }
-        ),
-    );
+            Name::new(monster_type.name.clone()),
+            NavigateToPlayer,
+            PathfindingGoal { goal: None },
+            MonsterAi::new(&monster_type.behaviour),
+            monster_type.get_perception(),
+            monster_type.get_hearing(),
+            monster_type.get_collider_shape(),
+            InterpolatedTransform::default(),
+            MonsterAnimations::new(assets.get_animation_graph(&monster_type.name))
+            ),
+        );
+    }
+}
+
+const CLOSED_DOOR_COST: usize = 8;
+
+// monsters can still get through closed doors, they just prefer going round through open ones
+pub fn get_monster_pathfinding_options() -> PathfindingOptions {
+    PathfindingOptions {
+        edge_costs: EdgeCosts { closed_doorway: CLOSED_DOOR_COST, ..default() },
+        through_closed_doors: true,
+        ..default()
+    }
+}
+// how far a monster can drift sideways off the line between cell centers before it straightens up,
+// otherwise it would cut corners heading for the next cell
+const CELL_CENTER_TOLERANCE: f32 = 0.25;
+
+// rebuilt whenever the player moves to a new cell, and patched up as doors open
+fn update_flow_field(
+    maze: Res<Maze>,
+    room_links: Res<RoomLinks>,
+    mut flow_field: ResMut<FlowField>,
+    player: Query<&Position, With<LogicalPlayer>>,
+    mut player_cell_changes: EventReader<PlayerCellChangeEvent>,
+    mut door_state_changes: EventReader<DoorStateChanged>
+) {
+    let player_position = *player.single();
+    if player_cell_changes.read().count() > 0 || flow_field.get_target() != Some(player_position) {
+        door_state_changes.clear();
+        flow_field.rebuild(maze.get_layout(), &room_links, player_position);
+        return;
+    }
+
+    for door_state_change in door_state_changes.read() {
+        if door_state_change.is_open {
+            flow_field.open_between(maze.get_layout(), &room_links, door_state_change.position, door_state_change.other_side);
+        } else {
+            flow_field.close_between(maze.get_layout(), &room_links);
+        }
+    }
+}
+
+fn update_monster_perception(
+    maze: Res<Maze>,
+    room_links: Res<RoomLinks>,
+    mut monsters: Query<(Entity, &Transform, &mut MonsterPerception, Option<&mut MonsterAi>)>,
+    player: Query<(&Transform, &Position), With<LogicalPlayer>>,
+    player_lights: Query<(&GlobalTransform, &SpotLight), With<PlayerLight>>,
+    mut seen_writer: EventWriter<PlayerSeen>,
+    mut lost_writer: EventWriter<PlayerLost>,
+    config: Res<MazeConfig>
+) {
+    let (player_transform, player_position) = player.single();
+    let player_at = player_transform.translation.xz();
+    for (monster, monster_transform, mut perception, ai) in monsters.iter_mut() {
+        let monster_at = monster_transform.translation.xz();
+        let to_player = player_at - monster_at;
+        let forward = (monster_transform.rotation * Vec3::Z).xz();
+
+        // lit if the monster is inside the cone of the player's light
+        let is_lit = player_lights.iter().any(|(light_transform, light)| {
+            let to_monster = monster_transform.translation - light_transform.translation();
+            light.intensity > 0.0
+                && to_monster.length() <= light.range
+                && light_transform.forward().angle_between(to_monster) <= light.outer_angle
+        });
+
+        let sees_player = to_player.length() / config.maze_scale <= perception.get_view_distance(is_lit)
+            && perception.is_in_view_cone(forward, to_player)
+            && has_line_of_sight(maze.get_layout(), &room_links, monster_at / config.maze_scale, player_at / config.maze_scale);
+
+        if perception.set_sees_player(sees_player) {
+            if sees_player {
+                seen_writer.send(PlayerSeen { monster });
+            } else {
+                lost_writer.send(PlayerLost { monster });
+            }
+        }
+        if let Some(mut ai) = ai {
+            ai.set_sees_player(sees_player, *player_position);
+        }
+    }
 }
 
-//Query<&Transform, (With<LogicalPlayer>, Without<TopDownCamera>)>
+// a monster that hears something goes to have a look, unless it is already chasing the player
+fn hear_noises(
+    maze: Res<Maze>,
+    room_links: Res<RoomLinks>,
+    mut noises: EventReader<NoiseEvent>,
+    mut monsters: Query<(&Transform, &MonsterHearing, &mut MonsterAi)>,
+    config: Res<MazeConfig>
+) {
+    let options = get_monster_pathfinding_options();
+    for noise in noises.read() {
+        let heard = noise.spread(maze.get_layout(), &room_links);
+        for (monster_transform, hearing, mut ai) in monsters.iter_mut() {
+            let monster_position = Position::get_from_transform(&monster_transform, config.maze_scale);
+            let loudness = heard.get(&(monster_position.x as i32, monster_position.y as i32)).copied().unwrap_or(0.0);
+            if loudness >= hearing.threshold {
+                ai.investigate(maze.get_layout(), &room_links, &options, monster_position, noise.position);
+            }
+        }
+    }
+}
+
+fn update_monster_ai(
+    time: Res<Time>,
+    maze: Res<Maze>,
+    room_links: Res<RoomLinks>,
+    mut rng: ResMut<Random>,
+    mut monsters: Query<(&Transform, &mut MonsterAi)>,
+    config: Res<MazeConfig>
+) {
+    let options = get_monster_pathfinding_options();
+    for (monster_transform, mut ai) in monsters.iter_mut() {
+        let monster_position = Position::get_from_transform(&monster_transform, config.maze_scale);
+        ai.update(time.delta(), maze.get_layout(), &room_links, &options, monster_position, &mut rng.0);
+    }
+}
+
+// Chasing monsters follow the flow field toward the player.  It replaces planning a route through the rooms for
+// each monster: it's rebuilt whenever the player changes cell, which covers changing room, and one field serves
+// every monster at once.  Monsters that aren't chasing still walk room routes, see monster_route
 fn pathfind_towards_player(
     maze: Res<Maze>,
-    mut monsters: Query<(&Transform, &mut PathfindingGoal), With<NavigateToPlayer>>,
+    room_links: Res<RoomLinks>,
+    flow_field: Res<FlowField>,
+    mut monsters: Query<(&Transform, &mut PathfindingGoal, Option<&MonsterAi>), With<NavigateToPlayer>>,
     player: Query<(&Transform, &Position), (With<LogicalPlayer>, Without<NavigateToPlayer>)>,
-    mut writer: EventWriter<MonsterReachedPlayer>
+    mut writer: EventWriter<MonsterReachedPlayer>,
+    config: Res<MazeConfig>
 ) {
     let (player_transform, player_position) = player.single();
-    let player_room = maze.get_room_number_for_position(player_position.clone());
-    for (monster_transform, mut goal) in monsters.iter_mut() {
-        let monster_position = Position::get_from_transform(&monster_transform, consts::MAZE_SCALE);
-        let monster_room = maze.get_room_number_for_position(monster_position);
-
-        if monster_room == player_room {
-            if monster_transform.translation.xz().distance(player_transform.translation.xz()) < 1.0 {
-                goal.goal = None;
-                writer.send(MonsterReachedPlayer);
-            } else if goal.goal.is_some_and(|goal_pos| goal_pos == player_transform.translation.xz()) {
-                // goal hasn't changed
+    for (monster_transform, mut goal, ai) in monsters.iter_mut() {
+        let monster_position = Position::get_from_transform(&monster_transform, config.maze_scale);
+
+        if monster_transform.translation.xz().distance(player_transform.translation.xz()) < 1.0 {
+            goal.goal = None;
+            writer.send(MonsterReachedPlayer);
+        } else if ai.is_some_and(|ai| ai.get_state() != MonsterState::Chase) {
+            // follow_monster_paths steers monsters that aren't chasing
+        } else if monster_position == *player_position {
+            // same cell, nothing left to route around so just go straight for the player
+            goal.goal = Some(player_transform.translation.xz());
+        } else {
+            goal.goal = flow_field.get_next_cell(maze.get_layout(), &room_links, monster_position).map(|next_cell| {
+                let cell_center = monster_position.to_vec3_by_scale(config.maze_scale).xz();
+                let next_center = next_cell.to_vec3_by_scale(config.maze_scale).xz();
+                let direction = (next_center - cell_center).normalize();
+                let offset = monster_transform.translation.xz() - cell_center;
+                if (offset - direction * offset.dot(direction)).length() > CELL_CENTER_TOLERANCE {
+                    cell_center
+                } else {
+                    next_center
+                }
+            });
+        }
+    }
+}
+
+// walks monsters along the cells their AI picked out, one cell center at a time
+fn follow_monster_paths(mut monsters: Query<(&Transform, &mut PathfindingGoal, &mut MonsterAi)>, config: Res<MazeConfig>) {
+    for (monster_transform, mut goal, mut ai) in monsters.iter_mut() {
+        if ai.get_state() == MonsterState::Chase {
+            continue;
+        }
+        while let Some(next_cell) = ai.get_next_cell() {
+            if monster_transform.translation.xz().distance(next_cell.to_vec3_by_scale(config.maze_scale).xz()) < CELL_CENTER_TOLERANCE {
+                ai.pop_next_cell();
             } else {
-                // set the pathfinding goal I guess to the player's position?
-                goal.goal = Some(player_transform.translation.xz());
-                println!("Changed goal to new position");
+                break;
             }
-        } else {
-            // work out how to get the monster into the player's room and set that as a goal
-            // if the player changes rooms we will need to update the goal.
         }
+        goal.goal = ai.get_next_cell().map(|next_cell| next_cell.to_vec3_by_scale(config.maze_scale).xz());
     }
 }
 
-fn move_agents_towards_goals(time: Res<Time>, mut agents: Query<(&mut Transform, &mut Velocity, &PathfindingGoal)>) {
-    for (mut agent_transform, mut agent_velocity, goal) in agents.iter_mut() {
+fn move_agents_towards_goals(time: Res<Time>, mut agents: Query<(&mut Transform, &mut Velocity, &PathfindingGoal, &Speed, Option<&MonsterAi>)>) {
+    for (mut agent_transform, mut agent_velocity, goal, speed, ai) in agents.iter_mut() {
         match goal.goal {
             Some(goal) => {
                 let move_direction = (goal - agent_transform.translation.xz()).normalize();
+                let speed_multiplier = ai.map_or(1.0, |ai| ai.get_state().get_speed_multiplier());
 
-                agent_velocity.set_velocity(Vec2::new(move_direction.x, move_direction.y) * 2.0);
+                agent_velocity.set_velocity(Vec2::new(move_direction.x, move_direction.y) * speed.0 * speed_multiplier);
         
                 rotate_towards_direction(agent_transform, move_direction, &time)        
             },
@@ -114,4 +297,38 @@ fn rotate_towards_direction(mut monster_transform: Mut<'_, Transform>, move_dire
     let max_angle = forward_dot_player.clamp(-1.0, 1.0).acos();
     let monster_angle = rotation_sign * (2.0 * time.delta_seconds()).min(max_angle);
     monster_transform.rotate_y(monster_angle);
-}
\ No newline at end of file
+}
+
+fn draw_monster_ai_gizmos(
+    mut gizmos: Gizmos,
+    controllers: Query<&Controller>,
+    monsters: Query<(&Transform, &PathfindingGoal, &MonsterAi, Option<&MonsterPerception>)>,
+    config: Res<MazeConfig>
+) {
+    if !controllers.iter().any(|controller| controller.draw_gizmos) {
+        return;
+    }
+    let lift = Vec3::Y * 0.1;
+    for (monster_transform, goal, ai, perception) in monsters.iter() {
+        let color = ai.get_state().get_gizmo_color();
+        if let Some(perception) = perception {
+            let view_color = if perception.sees_player() { MonsterState::Chase.get_gizmo_color() } else { Color::srgb(0.6, 0.6, 0.6) };
+            let view_length = perception.get_view_distance(false) * config.maze_scale;
+            for side in [-0.5, 0.5] {
+                let edge = Quat::from_rotation_y(side * perception.view_angle) * monster_transform.rotation * Vec3::Z;
+                gizmos.line(monster_transform.translation + lift, monster_transform.translation + lift + edge * view_length, view_color);
+            }
+        }
+        gizmos.circle(monster_transform.translation + lift, Dir3::Y, 0.75, color);
+        if let Some(goal) = goal.goal {
+            gizmos.line(monster_transform.translation + lift, Vec3::new(goal.x, 0., goal.y) + lift, color);
+        }
+        gizmos.linestrip(ai.get_path().iter().map(|cell| cell.to_vec3_by_scale(config.maze_scale) + lift), color);
+        for waypoint in ai.get_patrol_waypoints() {
+            gizmos.sphere(waypoint.to_vec3_by_scale(config.maze_scale) + lift, Quat::IDENTITY, 0.3, MonsterState::Patrol.get_gizmo_color());
+        }
+        if let MonsterState::Investigate(position) = ai.get_state() {
+            gizmos.circle(position.to_vec3_by_scale(config.maze_scale) + lift, Dir3::Y, 0.5, color);
+        }
+    }
+}
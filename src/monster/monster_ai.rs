@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{maze::{maze_layout::MazeLayout, room_links::RoomLinks}, pathfinding::{find_cell_path, PathfindingOptions}, position::Position};
+
+use super::monster_route::plan_room_route;
+
+// How a type of monster goes about hunting, set per type in the monster catalog
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonsterBehaviour {
+    // how many rooms a patrol goes through before looping back round
+    pub patrol_rooms: usize,
+    // how long the monster keeps chasing after it last saw the player
+    pub lose_sight_seconds: f32,
+    // how long it looks around where it lost the player before giving up
+    pub search_seconds: f32
+}
+
+impl Default for MonsterBehaviour {
+    fn default() -> Self {
+        MonsterBehaviour { patrol_rooms: 4, lose_sight_seconds: 2.0, search_seconds: 8.0 }
+    }
+}
+
+/**
+ * What a monster is doing at the moment.  Patrolling monsters loop through a few rooms, and go after the player
+ * as soon as they see them.  Once the player is out of sight for a bit the monster goes to where it last saw them,
+ * searches the room there, and then returns to its patrol.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+pub enum MonsterState {
+    #[default]
+    Patrol,
+    Investigate(Position),
+    Chase,
+    Search,
+    Return
+}
+
+impl MonsterState {
+    // fraction of the monster's Speed it moves at in this state
+    pub fn get_speed_multiplier(&self) -> f32 {
+        match self {
+            MonsterState::Patrol => 0.4,
+            MonsterState::Investigate(_) => 0.7,
+            MonsterState::Chase => 1.0,
+            MonsterState::Search => 0.4,
+            MonsterState::Return => 0.5
+        }
+    }
+
+    pub fn get_gizmo_color(&self) -> Color {
+        match self {
+            MonsterState::Patrol => Color::srgb(0.2, 0.8, 0.2),
+            MonsterState::Investigate(_) => Color::srgb(0.9, 0.8, 0.1),
+            MonsterState::Chase => Color::srgb(0.9, 0.1, 0.1),
+            MonsterState::Search => Color::srgb(0.9, 0.5, 0.1),
+            MonsterState::Return => Color::srgb(0.2, 0.5, 0.9)
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct MonsterAi {
+    state: MonsterState,
+    patrol_rooms: usize,
+    sees_player: bool,
+    last_seen_player_at: Option<Position>,
+    patrol_waypoints: Vec<Position>,
+    next_waypoint: usize,
+    // cells left to walk, planned room by room, for every state but Chase which follows the flow field instead
+    path: VecDeque<Position>,
+    lose_sight_timer: Timer,
+    search_timer: Timer
+}
+
+impl Default for MonsterAi {
+    fn default() -> Self {
+        MonsterAi::new(&MonsterBehaviour::default())
+    }
+}
+
+impl MonsterAi {
+    pub fn new(behaviour: &MonsterBehaviour) -> Self {
+        MonsterAi {
+            state: MonsterState::Patrol,
+            patrol_rooms: behaviour.patrol_rooms,
+            sees_player: false,
+            last_seen_player_at: None,
+            patrol_waypoints: vec![],
+            next_waypoint: 0,
+            path: VecDeque::new(),
+            lose_sight_timer: Timer::from_seconds(behaviour.lose_sight_seconds, TimerMode::Once),
+            search_timer: Timer::from_seconds(behaviour.search_seconds, TimerMode::Once)
+        }
+    }
+
+    pub fn get_state(&self) -> MonsterState {
+        self.state
+    }
+
+    pub fn sees_player(&self) -> bool {
+        self.sees_player
+    }
+
+    pub fn set_sees_player(&mut self, sees_player: bool, player_position: Position) {
+        self.sees_player = sees_player;
+        if sees_player {
+            self.last_seen_player_at = Some(player_position);
+        }
+    }
+
+    pub fn get_patrol_waypoints(&self) -> &Vec<Position> {
+        &self.patrol_waypoints
+    }
+
+    pub fn get_path(&self) -> &VecDeque<Position> {
+        &self.path
+    }
+
+    pub fn get_next_cell(&self) -> Option<Position> {
+        self.path.front().copied()
+    }
+
+    pub fn pop_next_cell(&mut self) {
+        self.path.pop_front();
+    }
+
+    // head over to look at something, unless already chasing the player
+    pub fn investigate(&mut self, layout: &MazeLayout, room_links: &RoomLinks, options: &PathfindingOptions, from: Position, position: Position) {
+        if self.state == MonsterState::Chase {
+            return;
+        }
+        self.state = MonsterState::Investigate(position);
+        self.walk_to(layout, room_links, options, from, position);
+    }
+
+    pub fn update(&mut self, delta: std::time::Duration, layout: &MazeLayout, room_links: &RoomLinks, options: &PathfindingOptions, from: Position, rng: &mut impl Rng) {
+        if self.sees_player {
+            self.lose_sight_timer.reset();
+            if self.state != MonsterState::Chase {
+                self.state = MonsterState::Chase;
+                self.path.clear();
+            }
+            return;
+        }
+
+        match self.state {
+            MonsterState::Chase => {
+                if self.lose_sight_timer.tick(delta).finished() {
+                    let last_seen = self.last_seen_player_at.unwrap_or(from);
+                    self.state = MonsterState::Investigate(last_seen);
+                    self.walk_to(layout, room_links, options, from, last_seen);
+                }
+            },
+            MonsterState::Investigate(_) => {
+                if self.path.is_empty() {
+                    self.state = MonsterState::Search;
+                    self.search_timer.reset();
+                }
+            },
+            MonsterState::Search => {
+                if self.search_timer.tick(delta).finished() {
+                    self.state = MonsterState::Return;
+                    match self.patrol_waypoints.get(self.next_waypoint).copied() {
+                        Some(waypoint) => self.walk_to(layout, room_links, options, from, waypoint),
+                        None => self.path.clear()
+                    }
+                } else if self.path.is_empty() {
+                    // wander to somewhere else in the room, unless the monster has ended up off the maze somehow
+                    let Some(room) = layout.get_cell(&from).map(|cell| cell.get_room_index()) else { return };
+                    let room_cells: Vec<Position> = layout.get_maze_rooms().get_room_cells(room)
+                        .map(|cell| cell.get_position())
+                        .collect();
+                    if let Some(cell) = room_cells.choose(rng) {
+                        self.walk_to(layout, room_links, options, from, *cell);
+                    }
+                }
+            },
+            MonsterState::Return => {
+                if self.path.is_empty() {
+                    self.state = MonsterState::Patrol;
+                }
+            },
+            MonsterState::Patrol => {
+                if self.patrol_waypoints.is_empty() {
+                    self.patrol_waypoints = choose_patrol_waypoints(layout, room_links, from, self.patrol_rooms, rng);
+                    self.next_waypoint = 0;
+                }
+                if self.path.is_empty() && !self.patrol_waypoints.is_empty() {
+                    // head for the current waypoint, and only move on to the next once it's been reached
+                    if from == self.patrol_waypoints[self.next_waypoint] {
+                        self.next_waypoint = (self.next_waypoint + 1) % self.patrol_waypoints.len();
+                    }
+                    self.walk_to(layout, room_links, options, from, self.patrol_waypoints[self.next_waypoint]);
+                }
+            }
+        }
+    }
+
+    // room by room through the doorways, or cell by cell over the whole maze if the rooms don't join up that way
+    fn walk_to(&mut self, layout: &MazeLayout, room_links: &RoomLinks, options: &PathfindingOptions, from: Position, to: Position) {
+        self.path = plan_room_route(layout, room_links, from, to)
+            .or_else(|| find_cell_path(layout, room_links, from, to, options))
+            .unwrap_or_default()
+            .into();
+    }
+}
+
+// A loop of rooms next to each other in the room graph, starting from the monster's room, with a random cell in each.
+// None at all if the monster isn't in a cell of the maze, it tries again next time
+fn choose_patrol_waypoints(layout: &MazeLayout, room_links: &RoomLinks, from: Position, patrol_rooms: usize, rng: &mut impl Rng) -> Vec<Position> {
+    let Some(mut room) = layout.get_cell(&from).map(|cell| cell.get_room_index()) else { return vec![] };
+    let mut rooms = vec![room];
+    while rooms.len() < patrol_rooms {
+        let neighbours: Vec<usize> = room_links.get_room_links(room).iter()
+            .filter_map(|room_link| room_link.get_other_room_position(room))
+            .map(|room_position| room_position.room)
+            .collect();
+        let unvisited: Vec<usize> = neighbours.iter().copied().filter(|neighbour| !rooms.contains(neighbour)).collect();
+        let Some(next) = unvisited.choose(rng).or(neighbours.choose(rng)).copied() else { break };
+        rooms.push(next);
+        room = next;
+    }
+
+    rooms.iter().filter_map(|room| {
+        let cells: Vec<Position> = layout.get_maze_rooms().get_room_cells(*room).map(|cell| cell.get_position()).collect();
+        cells.choose(rng).copied()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::maze::maze_import::test_layouts::{import, FIVE_ROOMS};
+
+    use super::*;
+
+    #[test]
+    fn patrols_every_waypoint_in_order() {
+        let layout = import(FIVE_ROOMS);
+        let options = PathfindingOptions { through_closed_doors: true, ..Default::default() };
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut ai = MonsterAi::default();
+        let (first, second) = (Position::new_from_i32(1, 1), Position::new_from_i32(4, 1));
+        ai.patrol_waypoints = vec![first, second];
+        let mut update = |ai: &mut MonsterAi, from: Position| ai.update(Duration::ZERO, &layout, layout.get_room_links(), &options, from, &mut rng);
+
+        // a fresh patrol starts with the first waypoint
+        update(&mut ai, Position::new_from_i32(0, 0));
+        assert_eq!(ai.get_path().back(), Some(&first));
+
+        // and moves on once it's there
+        ai.path.clear();
+        update(&mut ai, first);
+        assert_eq!(ai.get_path().back(), Some(&second));
+
+        // coming back to the waypoint it left from carries on from there, rather than skipping the next one
+        ai.state = MonsterState::Return;
+        ai.next_waypoint = 0;
+        ai.path.clear();
+        update(&mut ai, first);
+        assert_eq!(ai.get_state(), MonsterState::Patrol);
+        update(&mut ai, first);
+        assert_eq!(ai.get_path().back(), Some(&second));
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::{prelude::*, render::{mesh::skinning::SkinnedMesh, view::NoFrustumCulling}};
+use serde::{Deserialize, Serialize};
+
+use crate::{character::character::Speed, physics::velocity::Velocity, player::player::LogicalPlayer};
+
+use super::monster_ai::{MonsterAi, MonsterState};
+
+// below this fraction of its Speed a monster blends from idle to walking, above it from walking to running
+const WALK_SPEED_FRACTION: f32 = 0.4;
+// how close a chasing monster has to be to the player to swing at them
+const ATTACK_DISTANCE: f32 = 2.5;
+// how quickly the attack blends in and out, in weight per second
+const ATTACK_BLEND_SPEED: f32 = 4.0;
+
+// Which of the animations in a monster's glb to use for each movement, by their index in the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonsterAnimationClips {
+    pub idle: usize,
+    pub walk: usize,
+    pub run: usize,
+    pub attack: usize
+}
+
+impl Default for MonsterAnimationClips {
+    fn default() -> Self {
+        MonsterAnimationClips { idle: 0, walk: 1, run: 2, attack: 3 }
+    }
+}
+
+// A monster type's animation graph, one clip per movement straight under the root so they can all play at once and be blended
+#[derive(Clone)]
+pub struct MonsterAnimationGraph {
+    pub graph: Handle<AnimationGraph>,
+    idle: AnimationNodeIndex,
+    walk: AnimationNodeIndex,
+    run: AnimationNodeIndex,
+    attack: AnimationNodeIndex
+}
+
+impl MonsterAnimationGraph {
+    pub fn new(model: &str, clips: &MonsterAnimationClips, server: &AssetServer, graphs: &mut Assets<AnimationGraph>) -> Self {
+        let mut graph = AnimationGraph::new();
+        let root = graph.root;
+        let mut add_clip = |index: usize| graph.add_clip(server.load(GltfAssetLabel::Animation(index).from_asset(String::from(model))), 1.0, root);
+        let (idle, walk, run, attack) = (add_clip(clips.idle), add_clip(clips.walk), add_clip(clips.run), add_clip(clips.attack));
+        MonsterAnimationGraph { graph: graphs.add(graph), idle, walk, run, attack }
+    }
+
+    fn get_nodes(&self) -> [AnimationNodeIndex; 4] {
+        [self.idle, self.walk, self.run, self.attack]
+    }
+}
+
+/**
+ * Sits on the monster itself.  The AnimationPlayer is on an entity somewhere down inside the monster's scene,
+ * which only shows up once the scene has loaded, so animation_player stays None until then.
+ */
+#[derive(Component)]
+pub struct MonsterAnimations {
+    graph: MonsterAnimationGraph,
+    animation_player: Option<Entity>,
+    attack_weight: f32
+}
+
+impl MonsterAnimations {
+    pub fn new(graph: MonsterAnimationGraph) -> Self {
+        MonsterAnimations { graph, animation_player: None, attack_weight: 0.0 }
+    }
+}
+
+// Finds the monster a newly loaded AnimationPlayer belongs to, and starts every clip looping on it
+pub fn attach_monster_animation_players(
+    mut commands: Commands,
+    mut new_players: Query<(Entity, &mut AnimationPlayer), Added<AnimationPlayer>>,
+    parents: Query<&Parent>,
+    mut monsters: Query<&mut MonsterAnimations>
+) {
+    for (player_entity, mut animation_player) in new_players.iter_mut() {
+        let Some(monster) = parents.iter_ancestors(player_entity).find(|ancestor| monsters.contains(*ancestor)) else { continue };
+        let mut animations = monsters.get_mut(monster).unwrap();
+
+        commands.entity(player_entity).insert(animations.graph.graph.clone());
+        for node in animations.graph.get_nodes() {
+            animation_player.play(node).repeat().set_weight(0.0);
+        }
+        animations.animation_player = Some(player_entity);
+    }
+}
+
+// Idle, walk and run blend by how fast the monster is going, and attack fades in over them while it's on top of the player
+pub fn blend_monster_animations(
+    time: Res<Time>,
+    mut monsters: Query<(&Transform, &Velocity, &Speed, &mut MonsterAnimations, Option<&MonsterAi>)>,
+    mut animation_players: Query<&mut AnimationPlayer>,
+    player: Query<&Transform, With<LogicalPlayer>>
+) {
+    let player_transform = player.single();
+    for (monster_transform, velocity, speed, mut animations, ai) in monsters.iter_mut() {
+        let Some(mut animation_player) = animations.animation_player.and_then(|entity| animation_players.get_mut(entity).ok()) else { continue };
+
+        let is_attacking = ai.is_some_and(|ai| ai.get_state() == MonsterState::Chase)
+            && monster_transform.translation.xz().distance(player_transform.translation.xz()) < ATTACK_DISTANCE;
+        let attack_target = if is_attacking { 1.0 } else { 0.0 };
+        let blend_step = ATTACK_BLEND_SPEED * time.delta_seconds();
+        animations.attack_weight += (attack_target - animations.attack_weight).clamp(-blend_step, blend_step);
+
+        let speed_fraction = (velocity.length() / speed.0.max(f32::EPSILON)).clamp(0.0, 1.0);
+        let (idle, walk, run) = if speed_fraction <= WALK_SPEED_FRACTION {
+            let walk = speed_fraction / WALK_SPEED_FRACTION;
+            (1.0 - walk, walk, 0.0)
+        } else {
+            let run = (speed_fraction - WALK_SPEED_FRACTION) / (1.0 - WALK_SPEED_FRACTION);
+            (0.0, 1.0 - run, run)
+        };
+
+        let movement_weight = 1.0 - animations.attack_weight;
+        let weights = [idle * movement_weight, walk * movement_weight, run * movement_weight, animations.attack_weight];
+        for (node, weight) in animations.graph.get_nodes().into_iter().zip(weights) {
+            if let Some(active_animation) = animation_player.animation_mut(node) {
+                active_animation.set_weight(weight);
+            }
+        }
+    }
+}
+
+// Skinned meshes keep the bounding box of their bind pose, which doesn't follow the animation, so the camera
+// would cull them while they're still on screen.  There are only a few monsters so theirs just aren't culled,
+// any other skinned mesh in the maze is left alone
+pub fn disable_skinned_mesh_culling(
+    mut commands: Commands,
+    skinned_meshes: Query<Entity, Added<SkinnedMesh>>,
+    parents: Query<&Parent>,
+    monsters: Query<(), With<MonsterAnimations>>
+) {
+    for entity in skinned_meshes.iter() {
+        if parents.iter_ancestors(entity).any(|ancestor| monsters.contains(ancestor)) {
+            commands.entity(entity).insert(NoFrustumCulling);
+        }
+    }
+}
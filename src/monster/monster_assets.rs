@@ -1,18 +1,39 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
-use crate::assets::Assets;
 
+use super::monster_animation::MonsterAnimationGraph;
+use super::monster_catalog::MonsterCatalog;
+
+// One scene per model in the monster catalog, keyed by the model's file name,
+// and one animation graph per monster type, keyed by the type's name
 #[derive(Resource)]
 pub struct MonsterAssets {
-    pub demon_model: Handle<Scene>
+    pub models: HashMap<String, Handle<Scene>>,
+    pub animation_graphs: HashMap<String, MonsterAnimationGraph>
 }
 
 impl MonsterAssets {
     pub fn load_assets(
         mut commands: Commands,
         server: Res<AssetServer>,
+        mut graphs: ResMut<Assets<AnimationGraph>>,
+        catalog: Res<MonsterCatalog>
     ) {
-        commands.insert_resource(MonsterAssets {
-            demon_model: Assets::load_glb_asset(String::from("demon.glb"), server, Some(0))
-        });
+        let models = catalog.get_models().into_iter()
+            .map(|model| (String::from(model), server.load(format!("{}#Scene0", model))))
+            .collect();
+        let animation_graphs = catalog.monster_types.iter()
+            .map(|monster_type| (monster_type.name.clone(), MonsterAnimationGraph::new(&monster_type.model, &monster_type.animations, &server, &mut graphs)))
+            .collect();
+        commands.insert_resource(MonsterAssets { models, animation_graphs });
+    }
+
+    pub fn get_model(&self, model: &str) -> Handle<Scene> {
+        self.models.get(model).cloned().unwrap_or_else(|| panic!("Monster model {} was never loaded", model))
+    }
+
+    pub fn get_animation_graph(&self, monster_type: &str) -> MonsterAnimationGraph {
+        self.animation_graphs.get(monster_type).cloned().unwrap_or_else(|| panic!("Monster type {} has no animation graph", monster_type))
     }
 }
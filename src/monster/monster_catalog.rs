@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::physics::collider::ColliderShape;
+
+use super::monster_ai::MonsterBehaviour;
+use super::monster_animation::MonsterAnimationClips;
+use super::monster_perception::{MonsterHearing, MonsterPerception};
+
+pub const DEFAULT_CATALOG_PATH: &str = "monsters.ron";
+
+/**
+ * Every type of monster the game can spawn, loaded at startup from a RON file (monsters.ron unless
+ * --monsters says otherwise).  spawn_count monsters are spawned into each maze, with each one's type picked
+ * at random weighted by spawn_weight.  Distances are in cells and view_angle is in degrees.
+ *
+ * Monsters spawn spawn_distance cells of walking away from the player's start, unless a difficulty preset
+ * is picked with --difficulty <name>, in which case that preset's range is used instead.
+ */
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonsterCatalog {
+    pub spawn_count: usize,
+    pub spawn_distance: SpawnDistance,
+    pub difficulties: HashMap<String, DifficultyPreset>,
+    pub monster_types: Vec<MonsterType>
+}
+
+// How far a monster can start from the player, in cells walked.  No max means anywhere past min
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnDistance {
+    pub min: usize,
+    pub max: Option<usize>
+}
+
+impl Default for SpawnDistance {
+    fn default() -> Self {
+        SpawnDistance { min: 6, max: None }
+    }
+}
+
+impl SpawnDistance {
+    pub fn contains(&self, distance: usize) -> bool {
+        distance >= self.min && self.max.map_or(true, |max| distance <= max)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyPreset {
+    pub spawn_distance: SpawnDistance
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonsterType {
+    pub name: String,
+    // a glb in the assets folder
+    pub model: String,
+    pub animations: MonsterAnimationClips,
+    pub scale: f32,
+    // how wide the monster is when bumping into walls, it's square
+    pub collider_size: f32,
+    pub speed: f32,
+    pub view_distance: f32,
+    pub lit_view_distance: f32,
+    pub view_angle: f32,
+    pub notices_light: bool,
+    pub hearing_threshold: f32,
+    pub behaviour: MonsterBehaviour,
+    pub spawn_weight: f32
+}
+
+impl Default for MonsterType {
+    fn default() -> Self {
+        MonsterType {
+            name: String::from("Demon"),
+            model: String::from("demon.glb"),
+            animations: MonsterAnimationClips::default(),
+            scale: 2.2,
+            collider_size: 1.0,
+            speed: 5.0,
+            view_distance: 6.0,
+            lit_view_distance: 10.0,
+            view_angle: 120.0,
+            notices_light: true,
+            hearing_threshold: 1.0,
+            behaviour: MonsterBehaviour::default(),
+            spawn_weight: 1.0
+        }
+    }
+}
+
+impl Default for MonsterCatalog {
+    fn default() -> Self {
+        MonsterCatalog {
+            spawn_count: 1,
+            spawn_distance: SpawnDistance::default(),
+            difficulties: HashMap::new(),
+            monster_types: vec![MonsterType::default()]
+        }
+    }
+}
+
+impl MonsterType {
+    pub fn get_perception(&self) -> MonsterPerception {
+        MonsterPerception::new(self.view_distance, self.lit_view_distance, self.view_angle.to_radians(), self.notices_light)
+    }
+
+    pub fn get_collider_shape(&self) -> ColliderShape {
+        ColliderShape::new(self.collider_size, self.collider_size)
+    }
+
+    pub fn get_hearing(&self) -> MonsterHearing {
+        MonsterHearing { threshold: self.hearing_threshold }
+    }
+}
+
+impl MonsterCatalog {
+    pub fn load_from_args(args: &[String]) -> Result<Self, String> {
+        let mut catalog = match args.iter().position(|arg| arg == "--monsters") {
+            Some(index) => MonsterCatalog::load_from_file(args.get(index + 1).ok_or("--monsters needs a path")?)?,
+            // the default file is optional
+            None => MonsterCatalog::load_from_file(DEFAULT_CATALOG_PATH).unwrap_or_default()
+        };
+        if let Some(index) = args.iter().position(|arg| arg == "--difficulty") {
+            catalog.apply_difficulty(args.get(index + 1).ok_or("--difficulty needs a name")?)?;
+        }
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| format!("Could not read monster catalog {}: {}", path, error))?;
+        ron::from_str(&contents).map_err(|error| format!("Could not parse monster catalog {}: {}", path, error))
+    }
+
+    pub fn apply_difficulty(&mut self, name: &str) -> Result<(), String> {
+        let preset = self.difficulties.get(name).ok_or_else(|| {
+            let mut names: Vec<&String> = self.difficulties.keys().collect();
+            names.sort();
+            format!("Unknown difficulty {}, the monster catalog has {:?}", name, names)
+        })?;
+        self.spawn_distance = preset.spawn_distance;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, spawn_distance) in self.difficulties.iter().map(|(name, preset)| (name.as_str(), preset.spawn_distance)).chain([("spawn_distance", self.spawn_distance)]) {
+            if spawn_distance.max.is_some_and(|max| max < spawn_distance.min) {
+                return Err(format!("Monster catalog {} has a max below its min", name));
+            }
+        }
+        if self.spawn_count > 0 && self.monster_types.is_empty() {
+            return Err(String::from("Monster catalog has a spawn_count but no monster_types"));
+        }
+        // animation graphs are kept by monster type name, so two types with one name would share one
+        let mut names = HashSet::new();
+        for monster_type in &self.monster_types {
+            if !names.insert(monster_type.name.as_str()) {
+                return Err(format!("Monster catalog has more than one monster type called {}", monster_type.name));
+            }
+            if !monster_type.spawn_weight.is_finite() || monster_type.spawn_weight < 0.0 {
+                return Err(format!("Monster type {} needs a spawn_weight of 0 or more, got {}", monster_type.name, monster_type.spawn_weight));
+            }
+            // written so NaN fails too
+            let is_positive = |value: f32| value.is_finite() && value > 0.0;
+            if !is_positive(monster_type.speed) || !is_positive(monster_type.scale) || !is_positive(monster_type.collider_size) {
+                return Err(format!("Monster type {} needs a speed, scale and collider_size above 0", monster_type.name));
+            }
+        }
+        if self.spawn_count > 0 && self.monster_types.iter().all(|monster_type| monster_type.spawn_weight == 0.0) {
+            return Err(String::from("Monster catalog needs at least one monster type with a spawn_weight above 0"));
+        }
+        Ok(())
+    }
+
+    // every model the catalog uses, once each
+    pub fn get_models(&self) -> Vec<&str> {
+        let mut models: Vec<&str> = self.monster_types.iter().map(|monster_type| monster_type.model.as_str()).collect();
+        models.sort();
+        models.dedup();
+        models
+    }
+
+    pub fn choose_monster_type(&self, rng: &mut impl Rng) -> Option<&MonsterType> {
+        self.monster_types.choose_weighted(rng, |monster_type| monster_type.spawn_weight).ok()
+    }
+}
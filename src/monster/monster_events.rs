@@ -1,4 +1,15 @@
 use bevy::prelude::*;
 
 #[derive(Event)]
-pub struct MonsterReachedPlayer;
\ No newline at end of file
+pub struct MonsterReachedPlayer;
+
+// Sent when a monster spots the player, and when it stops being able to see them
+#[derive(Event)]
+pub struct PlayerSeen {
+    pub monster: Entity
+}
+
+#[derive(Event)]
+pub struct PlayerLost {
+    pub monster: Entity
+}
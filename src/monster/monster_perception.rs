@@ -0,0 +1,74 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+/**
+ * How well a monster sees.  Distances are in cells.  The player has to be in range, inside the view cone
+ * in front of the monster, and not behind a wall or closed door.  When the player's light is shining on the
+ * monster they stand out, so they can be seen from further away.
+ */
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct MonsterPerception {
+    pub view_distance: f32,
+    pub lit_view_distance: f32,
+    // the full width of the view cone, in radians
+    pub view_angle: f32,
+    pub notices_light: bool,
+    sees_player: bool
+}
+
+impl Default for MonsterPerception {
+    fn default() -> Self {
+        MonsterPerception {
+            view_distance: 6.0,
+            lit_view_distance: 10.0,
+            view_angle: PI * 2.0 / 3.0,
+            notices_light: true,
+            sees_player: false
+        }
+    }
+}
+
+impl MonsterPerception {
+    pub fn new(view_distance: f32, lit_view_distance: f32, view_angle: f32, notices_light: bool) -> Self {
+        MonsterPerception { view_distance, lit_view_distance, view_angle, notices_light, sees_player: false }
+    }
+
+    pub fn sees_player(&self) -> bool {
+        self.sees_player
+    }
+
+    // returns true if that changed whether the player is seen
+    pub fn set_sees_player(&mut self, sees_player: bool) -> bool {
+        let changed = self.sees_player != sees_player;
+        self.sees_player = sees_player;
+        changed
+    }
+
+    pub fn get_view_distance(&self, is_lit: bool) -> f32 {
+        if is_lit && self.notices_light {
+            self.lit_view_distance
+        } else {
+            self.view_distance
+        }
+    }
+
+    // forward is the way the monster faces, to_player runs from the monster to the player, both on the xz plane
+    pub fn is_in_view_cone(&self, forward: Vec2, to_player: Vec2) -> bool {
+        forward.angle_between(to_player).abs() <= self.view_angle / 2.0
+    }
+}
+
+// How quiet a noise can be by the time it reaches the monster's cell and still get noticed
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct MonsterHearing {
+    pub threshold: f32
+}
+
+impl Default for MonsterHearing {
+    fn default() -> Self {
+        MonsterHearing { threshold: 1.0 }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::maze::{maze_layout::MazeLayout, room_links::RoomLinks};
+use crate::position::Position;
+
+/**
+ * The cells to walk through to get from one cell to another, planned a room at a time: the room links say which
+ * rooms to go through, and inside each room the walk goes cell by cell to the doorway into the next one.
+ * Includes both ends, None if there's no way through.
+ *
+ * Monsters walk these when they aren't chasing.  Chasing monsters follow the flow field instead, which is shared
+ * between them all and already rebuilt whenever the player changes cell.
+ */
+pub fn plan_room_route(layout: &MazeLayout, room_links: &RoomLinks, from: Position, to: Position) -> Option<Vec<Position>> {
+    let from_room = layout.get_cell(&from)?.get_room_index();
+    let to_room = layout.get_cell(&to)?.get_room_index();
+    let room_path = room_links.find_room_path(from_room, to_room);
+    if room_path.is_empty() {
+        return None;
+    }
+
+    let mut cells = vec![];
+    let mut current = from;
+    for rooms in room_path.windows(2) {
+        let (room, next_room) = (rooms[0], rooms[1]);
+        // if there's more than one door into the next room, use the one closest to where the walk has got to
+        let (exit, entry) = room_links.get_room_links(room).iter()
+            .filter_map(|room_link| Some((room_link.get_room_position(room)?, room_link.get_other_room_position(room)?)))
+            .filter(|(_, entry)| entry.room == next_room)
+            .min_by_key(|(exit, _)| current.get_distance_to_position(exit.position))?;
+        cells.extend(layout.find_path_in_room(current, exit.position)?);
+        current = entry.position;
+    }
+    cells.extend(layout.find_path_in_room(current, to)?);
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::maze_import::test_layouts::{import, FIVE_ROOMS};
+
+    use super::*;
+
+    fn cells(points: &[(i32, i32)]) -> Vec<Position> {
+        points.iter().map(|(x, y)| Position::new_from_i32(*x, *y)).collect()
+    }
+
+    #[test]
+    fn walks_room_by_room_through_the_doorways() {
+        let layout = import(FIVE_ROOMS);
+        let route = plan_room_route(&layout, layout.get_room_links(), Position::new_from_i32(0, 0), Position::new_from_i32(4, 1));
+        assert_eq!(route, Some(cells(&[(0, 0), (0, 1), (1, 1), (2, 1), (3, 1), (4, 1)])));
+
+        // inside one room it's just the walk across it
+        let route = plan_room_route(&layout, layout.get_room_links(), Position::new_from_i32(1, 0), Position::new_from_i32(1, 1));
+        assert_eq!(route, Some(cells(&[(1, 0), (0, 0), (0, 1), (1, 1)])));
+    }
+
+    #[test]
+    fn no_route_off_the_maze() {
+        let layout = import(FIVE_ROOMS);
+        assert_eq!(plan_room_route(&layout, layout.get_room_links(), Position::new_from_i32(0, 0), Position::new_from_i32(9, 9)), None);
+    }
+}
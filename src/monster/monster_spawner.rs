@@ -0,0 +1,41 @@
+use bevy::log::warn;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::flow_field::FlowField;
+use crate::maze::{maze_layout::MazeLayout, room_links::RoomLinks};
+use crate::pathfinding::PathfindingOptions;
+use crate::position::Position;
+
+use super::monster_catalog::SpawnDistance;
+
+/**
+ * Picks up to count different cells for monsters to start in, going by how far they are to walk from the player's
+ * start rather than as the crow flies.  Cells that can't be walked to at all, the player's starting room and the
+ * exit are never used.  If too few cells are in range the rest come from the furthest cells that are allowed.
+ */
+pub fn choose_spawn_cells(layout: &MazeLayout, room_links: &RoomLinks, count: usize, spawn_distance: &SpawnDistance, rng: &mut impl Rng) -> Vec<Position> {
+    let start = layout.get_start();
+    let exit = layout.get_exit();
+    let start_room = layout.get_room_number_for_position(start);
+
+    // the walk is counted the same through open and closed doors, monsters get through either
+    let mut distances = FlowField::new(PathfindingOptions { through_closed_doors: true, ..Default::default() });
+    distances.rebuild(layout, room_links, start);
+
+    let mut allowed: Vec<(Position, usize)> = layout.get_positions().into_iter()
+        .filter(|position| *position != exit && layout.get_cell(position).is_some_and(|cell| cell.get_room_index() != start_room))
+        .filter_map(|position| Some((position, distances.get_distance(&position)?)))
+        .collect();
+
+    let in_range: Vec<Position> = allowed.iter().filter(|(_, distance)| spawn_distance.contains(*distance)).map(|(position, _)| *position).collect();
+    let mut spawn_cells: Vec<Position> = in_range.choose_multiple(rng, count).copied().collect();
+
+    if spawn_cells.len() < count {
+        let max = spawn_distance.max.map_or(String::from("any"), |max| max.to_string());
+        warn!("Only {} cells are {} to {} cells from the start, spawning the rest as far away as possible", in_range.len(), spawn_distance.min, max);
+        allowed.retain(|(position, _)| !spawn_cells.contains(position));
+        allowed.sort_by_key(|(_, distance)| std::cmp::Reverse(*distance));
+        spawn_cells.extend(allowed.iter().take(count - spawn_cells.len()).map(|(position, _)| *position));
+    }
+    spawn_cells
+}
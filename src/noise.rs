@@ -0,0 +1,77 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::prelude::*;
+
+use crate::maze::{maze_layout::MazeLayout, room_links::RoomLinks};
+use crate::pathfinding::{get_neighbours, EdgeCosts, PathfindingOptions};
+use crate::position::Position;
+
+// how much quieter a noise gets going through a closed door, on top of the cell it steps into
+pub const CLOSED_DOOR_ATTENUATION: usize = 4;
+
+/**
+ * Something made a sound in a cell.  Loudness is how many cells of open passage it carries across before dying out,
+ * each cell it travels takes 1 off, walls stop it and closed doors muffle it by CLOSED_DOOR_ATTENUATION more.
+ */
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct NoiseEvent {
+    pub position: Position,
+    pub loudness: f32
+}
+
+// A cell the noise has reached, ordered so the heap hands back the loudest first
+#[derive(PartialEq)]
+struct HeardAt {
+    loudness: f32,
+    position: Position
+}
+
+impl Eq for HeardAt {}
+
+impl PartialOrd for HeardAt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeardAt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.loudness.total_cmp(&other.loudness)
+    }
+}
+
+impl NoiseEvent {
+    // How loud the noise is in every cell it reaches, keyed by cell, only going as far as the noise carries
+    pub fn spread(&self, layout: &MazeLayout, room_links: &RoomLinks) -> HashMap<(i32, i32), f32> {
+        let options = PathfindingOptions {
+            edge_costs: EdgeCosts { closed_doorway: 1 + CLOSED_DOOR_ATTENUATION, ..default() },
+            through_closed_doors: true,
+            ..default()
+        };
+        let get_key = |position: &Position| (position.x as i32, position.y as i32);
+
+        let mut heard: HashMap<(i32, i32), f32> = HashMap::new();
+        if layout.get_cell(&self.position).is_none() || self.loudness <= 0.0 {
+            return heard;
+        }
+        heard.insert(get_key(&self.position), self.loudness);
+        let mut open = BinaryHeap::from([HeardAt { loudness: self.loudness, position: self.position }]);
+
+        while let Some(HeardAt { loudness, position }) = open.pop() {
+            if heard.get(&get_key(&position)).is_some_and(|louder| *louder > loudness) {
+                continue;
+            }
+            for (next, cost) in get_neighbours(layout, room_links, position, &options) {
+                let next_loudness = loudness - cost as f32;
+                if next_loudness <= 0.0 || heard.get(&get_key(&next)).is_some_and(|louder| *louder >= next_loudness) {
+                    continue;
+                }
+                heard.insert(get_key(&next), next_loudness);
+                open.push(HeardAt { loudness: next_loudness, position: next });
+            }
+        }
+        heard
+    }
+
+}
@@ -0,0 +1,233 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::maze::{maze_cell_edge::EdgeType, maze_layout::MazeLayout, room_links::RoomLinks};
+use crate::pathfinding_node::PathfindingNode;
+use crate::position::Position;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Heuristic {
+    #[default]
+    Manhattan,
+    Euclidean
+}
+
+impl Heuristic {
+    pub fn estimate(&self, from: Position, to: Position) -> usize {
+        match self {
+            Heuristic::Manhattan => from.get_distance_to_position(to),
+            // rounded down so it never guesses more than the real distance
+            Heuristic::Euclidean => from.get_as_vec2().distance(to.get_as_vec2()).floor() as usize
+        }
+    }
+}
+
+// What it costs to step across each kind of edge.  Walls are never crossed so they have no cost, and closed doors
+// only cost something when the search is allowed through them.
+// Anything below 1 lets the heuristic overshoot, so the path found might not be the shortest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeCosts {
+    pub passage: usize,
+    pub doorway: usize,
+    pub closed_doorway: usize
+}
+
+impl Default for EdgeCosts {
+    fn default() -> Self {
+        EdgeCosts { passage: 1, doorway: 1, closed_doorway: 1 }
+    }
+}
+
+impl EdgeCosts {
+    pub fn get_cost(&self, edge_type: Option<EdgeType>, is_open: bool) -> usize {
+        match edge_type {
+            None => self.passage,
+            Some(_) if is_open => self.doorway,
+            Some(_) => self.closed_doorway
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PathfindingOptions {
+    pub heuristic: Heuristic,
+    pub edge_costs: EdgeCosts,
+    // the most moves a path can take, None for no limit
+    pub max_length: Option<usize>,
+    // closed doors block the way unless this is set
+    pub through_closed_doors: bool
+}
+
+// The cells that can be stepped to from a position and what each step costs
+pub fn get_neighbours(layout: &MazeLayout, room_links: &RoomLinks, position: Position, options: &PathfindingOptions) -> Vec<(Position, usize)> {
+    let Some(cell) = layout.get_cell(&position) else { return vec![] };
+    let mut neighbours = vec![];
+    for (direction, edge) in cell.get_edges() {
+        let next = &position + direction.to_position_modifier();
+        if layout.get_cell(&next).is_none() {
+            continue;
+        }
+        let is_open = match edge {
+            None => true,
+            Some(edge) if edge.is_doorway() => edge.is_passable(room_links, &position),
+            Some(_) => continue
+        };
+        if !is_open && !options.through_closed_doors {
+            continue;
+        }
+        neighbours.push((next, options.edge_costs.get_cost(cell.get_edge_type(direction), is_open)));
+    }
+    neighbours
+}
+
+/**
+ * A* from one cell to another over the whole maze.  A move is allowed through a passage, or through a door
+ * that is passable and open (going by the room links, or any door with through_closed_doors), never through a wall.  Returns the cells walked through,
+ * including the start and goal, or None if the goal can't be reached within options.max_length moves.
+ */
+pub fn find_cell_path(layout: &MazeLayout, room_links: &RoomLinks, start: Position, goal: Position, options: &PathfindingOptions) -> Option<Vec<Position>> {
+    layout.get_cell(&start)?;
+    layout.get_cell(&goal)?;
+
+    let get_key = |position: &Position| (position.x as i32, position.y as i32);
+    let mut nodes: Vec<PathfindingNode<Position>> = vec![PathfindingNode::new(start, 0, options.heuristic.estimate(start, goal), 0, None)];
+    // lowest f first, ties go to whichever node was found first so the search doesn't depend on hashing
+    let mut open = BinaryHeap::from([Reverse((nodes[0].get_f(), 0))]);
+    let mut best_g: HashMap<(i32, i32), usize> = HashMap::from([(get_key(&start), 0)]);
+
+    while let Some(Reverse((_, node_index))) = open.pop() {
+        let position = *nodes[node_index].get_node();
+        let (g, steps) = (nodes[node_index].get_g(), nodes[node_index].get_steps());
+        if best_g.get(&get_key(&position)).is_some_and(|best| *best < g) {
+            // found a cheaper way here after this one was queued
+            continue;
+        }
+
+        if position == goal {
+            let mut path = vec![];
+            let mut current = Some(node_index);
+            while let Some(index) = current {
+                path.push(*nodes[index].get_node());
+                current = nodes[index].get_previous();
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        if options.max_length.is_some_and(|max_length| steps >= max_length) {
+            continue;
+        }
+
+        for (next, cost) in get_neighbours(layout, room_links, position, options) {
+            let next_g = g + cost;
+            if best_g.get(&get_key(&next)).is_some_and(|best| *best <= next_g) {
+                continue;
+            }
+            best_g.insert(get_key(&next), next_g);
+            nodes.push(PathfindingNode::new(next, next_g, options.heuristic.estimate(next, goal), steps + 1, Some(node_index)));
+            open.push(Reverse((nodes[nodes.len() - 1].get_f(), nodes.len() - 1)));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maze::maze_import::test_layouts::{import, HAIRPIN};
+
+    use super::*;
+
+    fn path(points: &[(i32, i32)]) -> Vec<Position> {
+        points.iter().map(|(x, y)| Position::new_from_i32(*x, *y)).collect()
+    }
+
+    // two ways to E: through two doors at the top, or one door along the bottom
+    const TWO_WAYS: &str = "
++-+-+-+
+|SDcDE|
++ +-+ +
+|a aDb|
++-+-+-+
+";
+
+    #[test]
+    fn goes_around_walls() {
+        let layout = import(HAIRPIN);
+        let start = Position::new_from_i32(0, 0);
+        let goal = Position::new_from_i32(0, 1);
+        let expected = path(&[(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (0, 1)]);
+
+        for heuristic in [Heuristic::Manhattan, Heuristic::Euclidean] {
+            let options = PathfindingOptions { heuristic, ..Default::default() };
+            assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, goal, &options), Some(expected.clone()));
+        }
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, start, &PathfindingOptions::default()), Some(vec![start]));
+    }
+
+    #[test]
+    fn heuristics_never_overestimate() {
+        let from = Position::new_from_i32(0, 0);
+        let to = Position::new_from_i32(3, 4);
+        assert_eq!(Heuristic::Manhattan.estimate(from, to), 7);
+        assert_eq!(Heuristic::Euclidean.estimate(from, to), 5);
+    }
+
+    #[test]
+    fn length_limit() {
+        let layout = import(HAIRPIN);
+        let start = Position::new_from_i32(0, 0);
+        let goal = Position::new_from_i32(0, 1);
+
+        let too_short = PathfindingOptions { max_length: Some(4), ..Default::default() };
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, goal, &too_short), None);
+        let just_enough = PathfindingOptions { max_length: Some(5), ..Default::default() };
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, goal, &just_enough).map(|path| path.len()), Some(6));
+    }
+
+    #[test]
+    fn closed_doors_block_the_way() {
+        let layout = import(TWO_WAYS);
+        let mut room_links = layout.get_room_links().clone();
+        let start = Position::new_from_i32(0, 0);
+        let goal = Position::new_from_i32(2, 0);
+
+        assert_eq!(find_cell_path(&layout, &room_links, start, goal, &PathfindingOptions::default()), None);
+
+        room_links.open_link_between(&Position::new_from_i32(1, 1), &Position::new_from_i32(2, 1));
+        let expected = path(&[(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+        assert_eq!(find_cell_path(&layout, &room_links, start, goal, &PathfindingOptions::default()), Some(expected));
+
+        let through_closed_doors = PathfindingOptions { through_closed_doors: true, ..Default::default() };
+        let expected = path(&[(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(find_cell_path(&layout, &room_links, start, goal, &through_closed_doors), Some(expected));
+    }
+
+    #[test]
+    fn door_cost_changes_the_route() {
+        let layout = import(TWO_WAYS);
+        let start = Position::new_from_i32(0, 0);
+        let goal = Position::new_from_i32(2, 0);
+
+        let cheap_doors = PathfindingOptions { through_closed_doors: true, ..Default::default() };
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, goal, &cheap_doors), Some(path(&[(0, 0), (1, 0), (2, 0)])));
+
+        let costly_doors = PathfindingOptions { edge_costs: EdgeCosts { passage: 1, doorway: 5, closed_doorway: 5 }, ..cheap_doors };
+        let expected = path(&[(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), start, goal, &costly_doors), Some(expected));
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let layout = import("
++-+-+
+|S|E|
++ + +
+|a|b|
++-+-+
+");
+        let options = PathfindingOptions { through_closed_doors: true, ..Default::default() };
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), Position::new_from_i32(0, 0), Position::new_from_i32(1, 0), &options), None);
+        assert_eq!(find_cell_path(&layout, layout.get_room_links(), Position::new_from_i32(0, 0), Position::new_from_i32(5, 5), &options), None);
+    }
+}
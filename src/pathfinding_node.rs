@@ -1,19 +1,47 @@
-pub struct PathfindingNode<'a, T> {
-    node: &'a T,
+// One step of an A* search.  previous is the index of the node this one was reached from,
+// in whatever list the search keeps its nodes in, and steps is how many moves it took to get here
+pub struct PathfindingNode<T> {
+    node: T,
     g: usize,
     h: usize,
-    previous: &'a T
+    steps: usize,
+    previous: Option<usize>
 }
 
-impl <'a, T> PathfindingNode<'a, T> {
+impl <T> PathfindingNode<T> {
     pub fn new(
-        node: &'a T,
+        node: T,
         g: usize,
         h: usize,
-        previous: &'a T
+        steps: usize,
+        previous: Option<usize>
     ) -> Self {
         PathfindingNode {
-            node, g, h, previous
+            node, g, h, steps, previous
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_node(&self) -> &T {
+        &self.node
+    }
+
+    pub fn get_g(&self) -> usize {
+        self.g
+    }
+
+    pub fn get_h(&self) -> usize {
+        self.h
+    }
+
+    pub fn get_f(&self) -> usize {
+        self.g + self.h
+    }
+
+    pub fn get_steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn get_previous(&self) -> Option<usize> {
+        self.previous
+    }
+}
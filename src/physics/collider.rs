@@ -1,57 +1,39 @@
 use bevy::{
     prelude::*,
-    math::bounding::Aabb2d
+    math::bounding::{Aabb2d, BoundingVolume}
 };
 
-use crate::maze::maze_cell_edge::WallPosition;
-use crate::player::player::LogicalPlayer;
+use crate::maze::maze_config::MazeConfig;
+use super::collider_grid::ColliderGrid;
 use super::velocity::Velocity;
-use crate::GameRunSet;
 
 use crate::maze::maze_direction::MazeDirection;
 use crate::consts;
 
-pub struct ColliderPlugin;
-impl Plugin for ColliderPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(Update, (check_for_collisions).in_set(GameRunSet));
-    }
-}
-
 #[derive(Component)]
 pub struct Collider;
 
-#[derive(Event, Default)]
-pub struct CollisionEvent;
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum CollisionOrientation {
-    HORIZONTAL,
-    VERTICAL
+// The box something that moves around takes up on the floor, centered on its transform.
+// Anything with one of these and a Velocity is moved by move_colliders instead of apply_velocity, and stopped by the walls
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ColliderShape {
+    pub half_size: Vec2
 }
 
-impl Collider {
-    /**
-     * Takes two colliders and says what side of the first collider is colliding with the second, if any
-     */
-    pub fn box_collision(moving_collider: Aabb2d, static_collider: Aabb2d) -> Option<MazeDirection> {
-        let vertical_collision_percentage = get_percentage_of_side_in_collision(moving_collider.min.y, static_collider.min.y, moving_collider.max.y, static_collider.max.y);
-        let horizontal_collision_percentage = get_percentage_of_side_in_collision(moving_collider.min.x, static_collider.min.x, moving_collider.max.x, static_collider.max.x);
-
-        let collision_orientation = if vertical_collision_percentage > horizontal_collision_percentage { CollisionOrientation::VERTICAL } else { CollisionOrientation::HORIZONTAL };
-
-        if is_collision_on_east(moving_collider, static_collider, collision_orientation) {
-            return Some(MazeDirection::EAST);
-        } else if is_collision_on_west(moving_collider, static_collider, collision_orientation) {
-            return Some(MazeDirection::WEST);
-        } else if is_collision_on_north(moving_collider, static_collider, collision_orientation) {
-            return Some(MazeDirection::NORTH);
-        } else if is_collision_on_south(moving_collider, static_collider, collision_orientation) {
-            return Some(MazeDirection::SOUTH);
-        }
-        None
+impl ColliderShape {
+    pub fn new(width: f32, length: f32) -> Self {
+        ColliderShape { half_size: Vec2::new(width / 2., length / 2.) }
     }
 
+    pub fn get_aabb2d(&self, transform: &Transform) -> Aabb2d {
+        Aabb2d::new(Vec2::new(transform.translation.x, transform.translation.z), self.half_size)
+    }
+}
+
+#[derive(Event, Default)]
+pub struct CollisionEvent;
+
+impl Collider {
     pub fn transform_to_aabb2d(transform: &Transform) -> Aabb2d {
         Aabb2d::new(
         Vec2::new(transform.translation.x, transform.translation.z),
@@ -59,10 +41,19 @@ impl Collider {
         )
     }
 
-    pub fn get_wall_aabb2d(transform: &GlobalTransform, wall_facing: &MazeDirection) -> Aabb2d {
-        let wall_size = get_wall_size(wall_facing);
+    // boxes covering a line on the floor, split up so a line at an angle isn't one big box
+    pub fn get_segment_aabb2ds(start: Vec2, end: Vec2, half_thickness: f32, pieces: usize) -> Vec<Aabb2d> {
+        (0..pieces).map(|piece| {
+            let piece_start = start.lerp(end, piece as f32 / pieces as f32);
+            let piece_end = start.lerp(end, (piece + 1) as f32 / pieces as f32);
+            Aabb2d { min: piece_start.min(piece_end) - Vec2::splat(half_thickness), max: piece_start.max(piece_end) + Vec2::splat(half_thickness) }
+        }).collect()
+    }
+
+    pub fn get_wall_aabb2d(transform: &GlobalTransform, wall_facing: &MazeDirection, maze_scale: f32) -> Aabb2d {
+        let wall_size = get_wall_size(wall_facing, maze_scale);
 
-        let mid_point = get_wall_midpoint(transform, wall_facing);
+        let mid_point = get_wall_midpoint(transform, wall_facing, maze_scale);
 
         Aabb2d::new(
             mid_point,
@@ -71,97 +62,288 @@ impl Collider {
     }
 }
 
-fn get_wall_size(wall_facing: &MazeDirection) -> Vec2 {
+fn get_wall_size(wall_facing: &MazeDirection, maze_scale: f32) -> Vec2 {
     
     match wall_facing {
-        MazeDirection::EAST => Vec2::new(consts::WALL_THICKNESS, consts::MAZE_SCALE / 2.),
-        MazeDirection::WEST => Vec2::new(consts::WALL_THICKNESS, consts::MAZE_SCALE / 2.),
-        MazeDirection::NORTH => Vec2::new(consts::MAZE_SCALE / 2., consts::WALL_THICKNESS ),
-        MazeDirection::SOUTH => Vec2::new(consts::MAZE_SCALE / 2., consts::WALL_THICKNESS ),
+        MazeDirection::EAST => Vec2::new(consts::WALL_THICKNESS, maze_scale / 2.),
+        MazeDirection::WEST => Vec2::new(consts::WALL_THICKNESS, maze_scale / 2.),
+        MazeDirection::NORTH => Vec2::new(maze_scale / 2., consts::WALL_THICKNESS ),
+        MazeDirection::SOUTH => Vec2::new(maze_scale / 2., consts::WALL_THICKNESS ),
     }
 }
 
-fn get_wall_midpoint(transform: &GlobalTransform, wall_facing: &MazeDirection) -> Vec2 {
+fn get_wall_midpoint(transform: &GlobalTransform, wall_facing: &MazeDirection, maze_scale: f32) -> Vec2 {
+    let half_cell = maze_scale / 2.;
     match wall_facing {
-        MazeDirection::NORTH => Vec2::new(transform.translation().x + 2.5, transform.translation().z),
-        MazeDirection::EAST =>  Vec2::new(transform.translation().x , transform.translation().z + 2.5),
-        MazeDirection::SOUTH => Vec2::new(transform.translation().x - 2.5, transform.translation().z),
-        MazeDirection::WEST =>  Vec2::new(transform.translation().x, transform.translation().z - 2.5)
+        MazeDirection::NORTH => Vec2::new(transform.translation().x + half_cell, transform.translation().z),
+        MazeDirection::EAST =>  Vec2::new(transform.translation().x , transform.translation().z + half_cell),
+        MazeDirection::SOUTH => Vec2::new(transform.translation().x - half_cell, transform.translation().z),
+        MazeDirection::WEST =>  Vec2::new(transform.translation().x, transform.translation().z - half_cell)
     }
 }
 
-fn value_inside_range(value: f32, min: f32, max: f32) -> bool {
-    min < value && value < max
-}
+// how far off a wall a mover is left after bumping into it, so sliding along the wall doesn't catch on it
+const SKIN: f32 = 0.001;
+// a move can slide off one wall into another, more than this and it's stuck in a corner
+const MAX_SLIDES: usize = 3;
 
-fn get_percentage_of_side_in_collision(a_min: f32, b_min: f32, a_max: f32, b_max: f32) -> f32 {
-    let a_size = a_max - a_min;
-    let last_point_of_a_in_b = f32::min(a_max, b_max);
-    let first_point_of_a_in_b = f32::max(a_min, b_min);
-    let amount_of_a_in_b = f32::max(last_point_of_a_in_b - first_point_of_a_in_b, 0.0);
-    amount_of_a_in_b / a_size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepHit {
+    // how far through the move the boxes touch, from 0 to 1
+    pub time: f32,
+    // which way the face that was hit points
+    pub normal: Vec2
 }
 
-fn is_collision_on_east(moving_collider: Aabb2d, static_collider: Aabb2d, collision_orientation: CollisionOrientation) -> bool {
-    value_inside_range(moving_collider.max.x, static_collider.min.x, static_collider.max.x) &&
-    (value_inside_range(moving_collider.min.y, static_collider.min.y, static_collider.max.y) ||
-    value_inside_range(moving_collider.max.y, static_collider.min.y, static_collider.max.y)) &&
-    collision_orientation == CollisionOrientation::VERTICAL
+/**
+ * Where a box moving in a straight line first touches a wall, if it does during the move.  The wall is grown by
+ * the moving box's size so it's the same as sending the box's center along a ray.
+ * Boxes that are only touching, or already overlapping, don't count, so a mover can always slide along or back out of a wall
+ */
+pub fn sweep_aabb(moving_collider: Aabb2d, motion: Vec2, static_collider: Aabb2d) -> Option<SweepHit> {
+    let start = moving_collider.center();
+    let half_size = moving_collider.half_size();
+    let min = static_collider.min - half_size;
+    let max = static_collider.max + half_size;
 
-}
+    let mut entry = Vec2::NEG_INFINITY;
+    let mut exit = Vec2::INFINITY;
+    for axis in 0..2 {
+        if motion[axis] == 0. {
+            // not moving this way, so it has to already be lined up with the wall
+            if start[axis] <= min[axis] || start[axis] >= max[axis] {
+                return None;
+            }
+        } else {
+            let to_min = (min[axis] - start[axis]) / motion[axis];
+            let to_max = (max[axis] - start[axis]) / motion[axis];
+            entry[axis] = to_min.min(to_max);
+            exit[axis] = to_min.max(to_max);
+        }
+    }
 
-fn is_collision_on_west(moving_collider: Aabb2d, static_collider: Aabb2d, collision_orientation: CollisionOrientation) -> bool {
-    value_inside_range(moving_collider.min.x, static_collider.min.x, static_collider.max.x) &&
-    (value_inside_range(moving_collider.min.y, static_collider.min.y, static_collider.max.y) ||
-    value_inside_range(moving_collider.max.y, static_collider.min.y, static_collider.max.y)) &&
-    collision_orientation == CollisionOrientation::VERTICAL
-}
+    let entry_time = entry.max_element();
+    let exit_time = exit.min_element();
+    if entry_time > exit_time || entry_time < 0. || entry_time >= 1. {
+        return None;
+    }
 
-fn is_collision_on_north(moving_collider: Aabb2d, static_collider: Aabb2d, collision_orientation: CollisionOrientation) -> bool {
-    value_inside_range(moving_collider.max.y, static_collider.min.y, static_collider.max.y) &&
-    (value_inside_range(moving_collider.min.x, static_collider.min.x, static_collider.max.x) ||
-    value_inside_range(moving_collider.max.x, static_collider.min.x, static_collider.max.x)) &&
-    collision_orientation == CollisionOrientation::HORIZONTAL
+    let normal = if entry.x > entry.y {
+        Vec2::new(-motion.x.signum(), 0.)
+    } else {
+        Vec2::new(0., -motion.y.signum())
+    };
+    Some(SweepHit { time: entry_time, normal })
 }
 
-fn is_collision_on_south(moving_collider: Aabb2d, static_collider: Aabb2d, collision_orientation: CollisionOrientation) -> bool {
-    value_inside_range(moving_collider.min.y, static_collider.min.y, static_collider.max.y) &&
-    (value_inside_range(moving_collider.min.x, static_collider.min.x, static_collider.max.x) ||
-    value_inside_range(moving_collider.max.x, static_collider.min.x, static_collider.max.x)) &&
-    collision_orientation == CollisionOrientation::HORIZONTAL
+/**
+ * Moves a box as far as it can go, and whenever it runs into a wall carries on with whatever of the move
+ * was along the wall.  Running into a corner where two walls meet uses up the move against both.
+ * Returns how far the box actually moved, and the normals of the walls it hit on the way
+ */
+pub fn move_and_slide(moving_collider: Aabb2d, motion: Vec2, static_colliders: &[Aabb2d]) -> (Vec2, Vec<Vec2>) {
+    let half_size = moving_collider.half_size();
+    let mut position = moving_collider.center();
+    let mut remaining = motion;
+    let mut normals = vec![];
+
+    for _ in 0..MAX_SLIDES {
+        if remaining.length_squared() <= f32::EPSILON * f32::EPSILON {
+            break;
+        }
+        let current = Aabb2d::new(position, half_size);
+        let first_hit = static_colliders.iter()
+            .filter_map(|static_collider| sweep_aabb(current, remaining, *static_collider))
+            .min_by(|a, b| a.time.total_cmp(&b.time));
+
+        let Some(hit) = first_hit else {
+            position += remaining;
+            break;
+        };
+        position += remaining * hit.time + hit.normal * SKIN;
+        let left_over = remaining * (1. - hit.time);
+        remaining = left_over - hit.normal * left_over.dot(hit.normal);
+        normals.push(hit.normal);
+    }
+
+    (position - moving_collider.center(), normals)
 }
 
-// right now this is checking every collider against the player.
-// however, for walls and doors, the only time a player is going to be able to collide with them is
-// when they are in the same cell as the collider.
-// Ergo, we should be able to to instead get the player's cell, and then check collision with the objects
-// for that cell
-pub(crate) fn check_for_collisions(
-    mut player_query: Query<(&mut Velocity, &Transform), With<LogicalPlayer>>,
-    collider_query: Query<(&GlobalTransform, &WallPosition), (With<Collider>, Without<LogicalPlayer>)>,
+// Moves everything with a collider shape by its velocity, sliding it along any walls in the way.
+// Only the walls filed in the collider grid along the move get checked, see ColliderGrid.
+// Velocity going into a wall is taken off too, so anything reading it afterwards sees what actually happened
+pub(crate) fn move_colliders(
+    mut moving_query: Query<(&mut Velocity, &mut Transform, &ColliderShape)>,
+    collider_grid: Res<ColliderGrid>,
+    config: Res<MazeConfig>,
+    time: Res<Time>
 ) {
-    let (mut player_velocity, player_transform) = player_query.single_mut();
+    for (mut velocity, mut transform, shape) in moving_query.iter_mut() {
+        let moving_collider = shape.get_aabb2d(&transform);
+        let motion = **velocity * time.delta_seconds();
 
-    let player_collider = Collider::transform_to_aabb2d(player_transform);
+        let end_collider = Aabb2d::new(moving_collider.center() + motion, shape.half_size);
+        let walls: Vec<Aabb2d> = collider_grid.get_nearby(moving_collider.merge(&end_collider), config.maze_scale)
+            .map(|(_, wall_collider)| *wall_collider)
+            .collect();
 
-    let mut number_of_collisions = 0;
+        let (moved, normals) = move_and_slide(moving_collider, motion, &walls);
+        transform.translation.x += moved.x;
+        transform.translation.z += moved.y;
+        for normal in normals {
+            let into_wall = velocity.dot(normal);
+            if into_wall < 0. {
+                let new_velocity = **velocity - normal * into_wall;
+                velocity.set_velocity(new_velocity);
+            }
+        }
+    }
+}
 
-    for (collider_transform, wall_position) in collider_query.iter() {
-        // need to get the dimensions of the wall and the dimensions of the player size
-        // then use those to determine if one is inside the other
-        let wall_collider = Collider::get_wall_aabb2d(collider_transform, wall_position);
-        let collision = Collider::box_collision(player_collider, wall_collider);
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
 
-        if let Some(collision) = collision {
-            // collision_events.send(CollisionEvent);
-            match collision {
-                MazeDirection::EAST => player_velocity.x = f32::min(player_velocity.x, 0.),
-                MazeDirection::WEST => player_velocity.x = f32::max(player_velocity.x, 0.),
-                MazeDirection::NORTH => player_velocity.y = f32::min(player_velocity.y, 0.),
-                MazeDirection::SOUTH => player_velocity.y = f32::max(player_velocity.y, 0.)
-            }
+    use bevy::ecs::system::RunSystemOnce;
 
-            number_of_collisions += 1;
+    use crate::maze::maze_cell_edge::WallPosition;
+    use crate::physics::collider_grid::update_collider_grid;
+
+    use super::*;
+
+    // a wall running north-south along x = 3, a cell long either side of z = 0
+    fn spawn_wall(world: &mut World) {
+        let maze_scale = world.resource::<MazeConfig>().maze_scale;
+        world.spawn((
+            Collider,
+            WallPosition(MazeDirection::EAST),
+            GlobalTransform::from_xyz(3., 0., -maze_scale / 2.)
+        ));
+    }
+
+    fn spawn_monster(world: &mut World, velocity: Vec2) -> Entity {
+        world.spawn((
+            Transform::from_xyz(0., 0., 0.),
+            Velocity::new(velocity.x, velocity.y),
+            ColliderShape::new(1., 1.)
+        )).id()
+    }
+
+    // a monster's AI sets its velocity again every frame, so keep pushing it into the wall
+    fn run_frames(world: &mut World, monster: Entity, velocity: Vec2, frames: usize, frame_seconds: f32) {
+        for _ in 0..frames {
+            world.get_mut::<Velocity>(monster).unwrap().set_velocity(velocity);
+            world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(frame_seconds));
+            world.run_system_once(move_colliders);
         }
     }
-}
\ No newline at end of file
+
+    fn new_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(MazeConfig::default());
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(ColliderGrid::default());
+        spawn_wall(&mut world);
+        world.run_system_once(update_collider_grid);
+        assert_eq!(world.resource::<ColliderGrid>().len(), 1);
+        world
+    }
+
+    #[test]
+    fn monster_stops_at_wall() {
+        let mut world = new_world();
+        let velocity = Vec2::new(4., 0.);
+        let monster = spawn_monster(&mut world, velocity);
+
+        run_frames(&mut world, monster, velocity, 120, 1. / 60.);
+        let stopped_at = world.get::<Transform>(monster).unwrap().translation;
+        assert!((stopped_at.x - (3. - consts::WALL_THICKNESS - 0.5)).abs() < 0.01, "monster should stop against the wall, got to {}", stopped_at.x);
+
+        run_frames(&mut world, monster, velocity, 30, 1. / 60.);
+        assert_eq!(world.get::<Transform>(monster).unwrap().translation, stopped_at);
+    }
+
+    #[test]
+    fn monster_slides_along_wall() {
+        let mut world = new_world();
+        let velocity = Vec2::new(4., 1.);
+        let monster = spawn_monster(&mut world, velocity);
+
+        run_frames(&mut world, monster, velocity, 60, 1. / 60.);
+        let translation = world.get::<Transform>(monster).unwrap().translation;
+        assert!(translation.x + 0.5 < 3. + consts::WALL_THICKNESS);
+        assert!(translation.z > 0.9, "monster should keep moving along the wall, got to {}", translation.z);
+    }
+
+    #[test]
+    fn no_tunnelling_at_low_frame_rate() {
+        let mut world = new_world();
+        let velocity = Vec2::new(8., 0.);
+        let monster = spawn_monster(&mut world, velocity);
+
+        // a whole second in one frame would have gone 5 units straight through the wall
+        run_frames(&mut world, monster, velocity, 1, 1.);
+        let translation = world.get::<Transform>(monster).unwrap().translation;
+        assert!(translation.x + 0.5 <= 3. - consts::WALL_THICKNESS, "monster tunnelled to {}", translation.x);
+        assert_eq!(world.get::<Velocity>(monster).unwrap().x, 0.);
+    }
+
+    fn wall(center: (f32, f32), half_size: (f32, f32)) -> Aabb2d {
+        Aabb2d::new(Vec2::new(center.0, center.1), Vec2::new(half_size.0, half_size.1))
+    }
+
+    fn mover(x: f32, y: f32) -> Aabb2d {
+        Aabb2d::new(Vec2::new(x, y), Vec2::splat(0.5))
+    }
+
+    #[test]
+    fn sweep_finds_first_touch() {
+        let hit = sweep_aabb(mover(0., 0.), Vec2::new(4., 0.), wall((3., 0.), (0.5, 2.)));
+        assert_eq!(hit, Some(SweepHit { time: 0.5, normal: Vec2::new(-1., 0.) }));
+        // moving away, too short, or passing by to the side
+        assert_eq!(sweep_aabb(mover(0., 0.), Vec2::new(-4., 0.), wall((3., 0.), (0.5, 2.))), None);
+        assert_eq!(sweep_aabb(mover(0., 0.), Vec2::new(1., 0.), wall((3., 0.), (0.5, 2.))), None);
+        assert_eq!(sweep_aabb(mover(0., 5.), Vec2::new(4., 0.), wall((3., 0.), (0.5, 2.))), None);
+    }
+
+    #[test]
+    fn slides_over_seams() {
+        // two wall boxes end to end along y = 1, sliding along them shouldn't catch where they meet
+        let walls = [wall((0., 1.), (1., 0.2)), wall((2., 1.), (1., 0.2))];
+        let (moved, normals) = move_and_slide(mover(-0.5, 0.), Vec2::new(3., 1.), &walls);
+        assert!((moved.x - 3.).abs() < 0.001, "got stuck at {}", moved.x);
+        assert!((moved.y - 0.3).abs() < 0.01);
+        assert_eq!(normals, vec![Vec2::new(0., -1.)]);
+
+        // already up against the walls, running straight along them
+        let start = mover(-0.5, 0.8 - 0.5 - SKIN);
+        let (moved, normals) = move_and_slide(start, Vec2::new(3., 0.), &walls);
+        assert_eq!(moved, Vec2::new(3., 0.));
+        assert!(normals.is_empty());
+    }
+
+    #[test]
+    fn stops_in_inside_corner() {
+        // an L where a wall along x = 2 meets one along y = 2
+        let walls = [wall((2., 0.), (0.2, 2.2)), wall((0., 2.), (2.2, 0.2))];
+        let (moved, normals) = move_and_slide(mover(0., 0.), Vec2::new(5., 5.), &walls);
+        assert!((moved.x - 1.3).abs() < 0.01 && (moved.y - 1.3).abs() < 0.01, "ended at {}", moved);
+        assert_eq!(normals.len(), 2);
+    }
+
+    #[test]
+    fn slides_round_outside_corner() {
+        // the end of a wall along x = 2, stopping at y = 0.  Coming in diagonally just below the end, it slides up
+        // the wall face and past the end without catching on the corner
+        let walls = [wall((2., -2.), (0.2, 2.))];
+        let (moved, normals) = move_and_slide(mover(0., -1.), Vec2::new(4., 2.), &walls);
+        assert!((moved.x - 1.3).abs() < 0.01, "ended at {}", moved);
+        assert!((moved.y - 2.).abs() < 0.001, "ended at {}", moved);
+        assert_eq!(normals, vec![Vec2::new(-1., 0.)]);
+
+        // heading the other way it clears the corner before reaching the wall
+        let (moved, normals) = move_and_slide(mover(0., -1.), Vec2::new(2., 3.), &walls);
+        assert_eq!(moved, Vec2::new(2., 3.));
+        assert!(normals.is_empty());
+    }
+}
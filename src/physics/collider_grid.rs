@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    math::bounding::{Aabb2d, BoundingVolume}
+};
+
+use crate::maze::maze_cell_edge::WallPosition;
+use crate::maze::maze_config::MazeConfig;
+use crate::maze::maze_door::{MazeDoor, MazeDoorway};
+
+use super::collider::Collider;
+
+/**
+ * Every wall's box, and the boxes of doors and the doorway frames round them, filed under the maze cell its middle is in.  Walls sit on the line between two cells so
+ * they could end up under either one, but anything touching a wall is always within a cell of both.
+ * So when checking collisions only the boxes in the cells a mover passes through and the cells round them
+ * need looking at, instead of every wall in the maze.
+ */
+#[derive(Resource, Default)]
+pub struct ColliderGrid {
+    cells: HashMap<(i32, i32), Vec<(Entity, Aabb2d)>>,
+    // where each entity's boxes were filed, so they can be taken out again when it moves or goes away
+    entity_cells: HashMap<Entity, Vec<(i32, i32)>>
+}
+
+impl ColliderGrid {
+    pub fn get_cell(point: Vec2, maze_scale: f32) -> (i32, i32) {
+        ((point.x / maze_scale).round() as i32, (point.y / maze_scale).round() as i32)
+    }
+
+    // replaces whatever boxes the entity had before
+    pub fn insert(&mut self, entity: Entity, aabbs: &[Aabb2d], maze_scale: f32) {
+        self.remove(entity);
+        let mut cells = vec![];
+        for aabb in aabbs {
+            let cell = ColliderGrid::get_cell(aabb.center(), maze_scale);
+            self.cells.entry(cell).or_default().push((entity, *aabb));
+            cells.push(cell);
+        }
+        self.entity_cells.insert(entity, cells);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(cells) = self.entity_cells.remove(&entity) else { return };
+        for cell in cells {
+            if let Some(colliders) = self.cells.get_mut(&cell) {
+                colliders.retain(|(collider, _)| *collider != entity);
+            }
+        }
+    }
+
+    // the boxes that could be touching anything inside this area
+    pub fn get_nearby(&self, area: Aabb2d, maze_scale: f32) -> impl Iterator<Item = &(Entity, Aabb2d)> {
+        let (min_x, min_y) = ColliderGrid::get_cell(area.min, maze_scale);
+        let (max_x, max_y) = ColliderGrid::get_cell(area.max, maze_scale);
+        (min_x - 1..=max_x + 1).flat_map(move |x| (min_y - 1..=max_y + 1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entity_cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_cells.is_empty()
+    }
+}
+
+// walls only get their real GlobalTransform the frame after they're spawned, so they're filed once it changes.
+// Doors go by their own Transform under the doorway instead, as that's moved every tick while the GlobalTransform
+// only catches up at the end of the frame
+pub(crate) fn update_collider_grid(
+    mut grid: ResMut<ColliderGrid>,
+    walls: Query<(Entity, &GlobalTransform, &WallPosition), (With<Collider>, Changed<GlobalTransform>)>,
+    doorways: Query<(Entity, &GlobalTransform), (With<Collider>, With<MazeDoorway>, Changed<GlobalTransform>)>,
+    doors: Query<(Entity, &Transform, &Parent, &MazeDoor), (With<Collider>, Or<(Changed<Transform>, Changed<MazeDoor>)>)>,
+    parents: Query<&GlobalTransform>,
+    mut removed: RemovedComponents<Collider>,
+    config: Res<MazeConfig>
+) {
+    for entity in removed.read() {
+        grid.remove(entity);
+    }
+    for (entity, transform, wall_position) in walls.iter() {
+        grid.insert(entity, &[Collider::get_wall_aabb2d(transform, wall_position, config.maze_scale)], config.maze_scale);
+    }
+    for (entity, transform) in doorways.iter() {
+        grid.insert(entity, &MazeDoor::get_doorway_aabb2ds(transform), config.maze_scale);
+    }
+    for (entity, transform, parent, door) in doors.iter() {
+        let Ok(doorway_transform) = parents.get(parent.get()) else { continue };
+        grid.insert(entity, &door.get_aabb2ds(&doorway_transform.mul_transform(*transform)), config.maze_scale);
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Pose {
+    translation: Vec3,
+    rotation: Quat
+}
+
+impl Pose {
+    fn from_transform(transform: &Transform) -> Self {
+        Pose { translation: transform.translation, rotation: transform.rotation }
+    }
+
+    fn lerp(&self, other: &Pose, fraction: f32) -> Self {
+        Pose { translation: self.translation.lerp(other.translation, fraction), rotation: self.rotation.slerp(other.rotation, fraction) }
+    }
+
+    fn apply_to(&self, transform: &mut Transform) {
+        transform.translation = self.translation;
+        transform.rotation = self.rotation;
+    }
+}
+
+/**
+ * Movement, physics and doors run in FixedUpdate, which can tick zero, one or a few times in a frame.  Drawing
+ * things at wherever the last tick left them would stutter, so between ticks their Transform is set part of the way
+ * from where the last tick started to where it finished, by how far the clock has got towards the next tick.
+ * Before every tick the Transform is put back to where physics really left it.
+ *
+ * Anything moving the Transform outside of FixedUpdate (putting the player at the start, spawning) is spotted
+ * because the Transform isn't where this last left it, and is kept as is.
+ */
+#[derive(Component, Default)]
+pub struct InterpolatedTransform {
+    previous: Pose,
+    current: Pose,
+    // where this last put the Transform
+    rendered: Pose
+}
+
+impl InterpolatedTransform {
+    fn keep_outside_changes(&mut self, transform: &Transform) {
+        if transform.translation != self.rendered.translation {
+            self.previous.translation = transform.translation;
+            self.current.translation = transform.translation;
+        }
+        if transform.rotation != self.rendered.rotation {
+            self.previous.rotation = transform.rotation;
+            self.current.rotation = transform.rotation;
+        }
+    }
+
+    // where physics has it, rather than where it's being drawn
+    pub fn get_translation(&self) -> Vec3 {
+        self.current.translation
+    }
+}
+
+// FixedFirst, puts everything back where the last tick left it
+pub fn restore_physics_transforms(mut query: Query<(&mut Transform, &mut InterpolatedTransform)>) {
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.keep_outside_changes(&transform);
+        interpolated.previous = interpolated.current;
+        interpolated.current.apply_to(&mut transform);
+        interpolated.rendered = interpolated.current;
+    }
+}
+
+// FixedLast, remembers where this tick left everything
+pub fn record_physics_transforms(mut query: Query<(&Transform, &mut InterpolatedTransform)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = Pose::from_transform(transform);
+        interpolated.rendered = interpolated.current;
+    }
+}
+
+// Update, before anything draws or follows the Transform
+pub fn interpolate_transforms(fixed_time: Res<Time<Fixed>>, mut query: Query<(&mut Transform, &mut InterpolatedTransform)>) {
+    let fraction = fixed_time.overstep_fraction();
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.keep_outside_changes(&transform);
+        let pose = interpolated.previous.lerp(&interpolated.current, fraction);
+        pose.apply_to(&mut transform);
+        interpolated.rendered = pose;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+
+    use crate::maze::maze_config::MazeConfig;
+    use crate::physics::collider_grid::ColliderGrid;
+    use crate::physics::collider::{move_colliders, ColliderShape};
+    use crate::physics::velocity::Velocity;
+
+    use super::*;
+
+    const TICK_RATE: f64 = 64.;
+
+    fn new_app(frame_seconds: f32) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(frame_seconds)))
+            .insert_resource(MazeConfig::default())
+            .init_resource::<ColliderGrid>()
+            .add_systems(FixedFirst, restore_physics_transforms)
+            .add_systems(FixedUpdate, move_colliders)
+            .add_systems(FixedLast, record_physics_transforms)
+            .add_systems(Update, interpolate_transforms);
+        let mover = app.world_mut().spawn((
+            Transform::from_xyz(0., 0., 0.),
+            Velocity::new(3., 1.),
+            ColliderShape::new(1., 1.),
+            InterpolatedTransform::default()
+        )).id();
+        (app, mover)
+    }
+
+    // run the app until the fixed clock has ticked this many times
+    fn run_ticks(app: &mut App, ticks: u32) {
+        let tick = Duration::from_secs_f64(1. / TICK_RATE);
+        while app.world().resource::<Time<Fixed>>().elapsed() < tick * ticks {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn same_movement_at_any_frame_rate() {
+        let (mut slow_app, slow_mover) = new_app(1. / 20.);
+        let (mut fast_app, fast_mover) = new_app(1. / 144.);
+        run_ticks(&mut slow_app, 64);
+        run_ticks(&mut fast_app, 64);
+
+        let slow = slow_app.world().get::<InterpolatedTransform>(slow_mover).unwrap().get_translation();
+        let fast = fast_app.world().get::<InterpolatedTransform>(fast_mover).unwrap().get_translation();
+        assert_eq!(slow, fast);
+        assert!((slow - Vec3::new(3., 0., 1.)).length() < 0.001, "moved to {}", slow);
+    }
+
+    #[test]
+    fn drawn_between_ticks() {
+        let (mut app, mover) = new_app(1. / 20.);
+        run_ticks(&mut app, 10);
+
+        let interpolated = app.world().get::<InterpolatedTransform>(mover).unwrap();
+        let drawn_at = app.world().get::<Transform>(mover).unwrap().translation;
+        assert!(drawn_at.x >= interpolated.previous.translation.x && drawn_at.x <= interpolated.current.translation.x);
+        let fraction = app.world().resource::<Time<Fixed>>().overstep_fraction();
+        assert!((drawn_at - interpolated.previous.translation.lerp(interpolated.current.translation, fraction)).length() < 0.0001);
+    }
+}
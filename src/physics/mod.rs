@@ -1,3 +1,5 @@
 pub mod collider;
 pub mod velocity;
-pub mod physics;
\ No newline at end of file
+pub mod physics;
+pub mod collider_grid;
+pub mod interpolation;
\ No newline at end of file
@@ -3,12 +3,19 @@ use bevy::prelude::*;
 use crate::game_states::GameState;
 
 use super::velocity::apply_velocity;
-use super::collider::check_for_collisions;
+use super::collider::move_colliders;
+use super::collider_grid::{update_collider_grid, ColliderGrid};
+use super::interpolation::{interpolate_transforms, record_physics_transforms, restore_physics_transforms};
 
 pub struct PhysicsPlugin;
 
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (check_for_collisions, apply_velocity).chain().run_if(in_state(GameState::InGame)));
+        // the tick rate comes from the MazeConfig, see main
+        app.init_resource::<ColliderGrid>()
+            .add_systems(FixedFirst, restore_physics_transforms)
+            .add_systems(FixedUpdate, (update_collider_grid, move_colliders, apply_velocity).chain().run_if(in_state(GameState::InGame)))
+            .add_systems(FixedLast, record_physics_transforms)
+            .add_systems(Update, interpolate_transforms);
     }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::game_states::GameState;
+use super::collider::ColliderShape;
 
 #[derive(Component, Deref, DerefMut)]
 pub struct Velocity(Vec2);
@@ -20,15 +20,8 @@ impl Velocity {
     }
 }
 
-pub struct VelocityPlugin;
-
-impl Plugin for VelocityPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(Update, (apply_velocity).run_if(in_state(GameState::InGame)));
-    }
-}
-
-pub fn apply_velocity(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time>) {
+// anything with a collider shape is moved by move_colliders instead, so it can't go through walls
+pub fn apply_velocity(mut query: Query<(&mut Transform, &Velocity), Without<ColliderShape>>, time: Res<Time>) {
     for (mut transform, velocity) in &mut query {
         transform.translation.x += velocity.x * time.delta_seconds();
         transform.translation.z += velocity.y * time.delta_seconds();
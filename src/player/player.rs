@@ -8,10 +8,14 @@ use bevy::render::view::RenderLayers;
 
 use crate::character::character::{CharacterBundle, Speed};
 use crate::game_states::GameState;
-use crate::physics::velocity;
+use crate::maze::maze::Maze;
+use crate::maze::maze_config::MazeConfig;
 use crate::position::Position;
 use crate::consts;
+use crate::noise::NoiseEvent;
 use crate::physics::velocity::Velocity;
+use crate::physics::collider::{move_colliders, ColliderShape};
+use crate::physics::interpolation::{interpolate_transforms, InterpolatedTransform};
 
 use super::player_events::PlayerCellChangeEvent;
 
@@ -36,6 +40,10 @@ pub struct CameraConfig {
     pub height_offset: f32,
 }
 
+// the spotlight the player carries around
+#[derive(Component)]
+pub struct PlayerLight;
+
 #[derive(Component)]
 pub struct Controller {
     pub pitch: f32,
@@ -64,6 +72,7 @@ pub struct ControllerInput {
     pub pitch: f32,
     pub yaw: f32,
     pub movement: Vec3,
+    pub sneak: bool,
     pub mouse_look: bool,
     pub draw_gizmos: bool
 }
@@ -74,6 +83,7 @@ impl Default for ControllerInput {
             pitch: 0.0,
             yaw: 0.0,
             movement: Vec3::ZERO,
+            sneak: false,
             mouse_look: true,
             draw_gizmos: false
         }
@@ -85,8 +95,13 @@ impl Plugin for PlayerPlugin {
         use bevy::input::{keyboard, mouse};
 
         app.add_systems(Startup, setup)
-            .add_systems(PreUpdate, (controller_input, controller_look, controller_move, controller_render).chain().after(mouse::mouse_button_input_system).after(keyboard::keyboard_input_system))
-            .add_systems(Update, (check_cell_changed).after(velocity::apply_velocity).run_if(in_state(GameState::InGame)))
+            .add_systems(PreUpdate, (controller_input, controller_look).chain().after(mouse::mouse_button_input_system).after(keyboard::keyboard_input_system))
+            .add_systems(FixedUpdate, controller_move.before(move_colliders))
+            // the camera follows where the player is drawn, not where physics last left them
+            .add_systems(Update, controller_render.after(interpolate_transforms))
+            .add_systems(OnEnter(GameState::InGame), move_player_to_start)
+            // the cell the player is in goes by where physics put them, not where they're drawn
+            .add_systems(FixedUpdate, (check_cell_changed, make_footstep_noise).chain().after(move_colliders).run_if(in_state(GameState::InGame)))
             .add_event::<PlayerCellChangeEvent>();
     }
 }
@@ -106,13 +121,14 @@ fn get_axis(key_input: &Res<ButtonInput<KeyCode>>, key_pos: KeyCode, key_neg: Ke
 fn setup (    
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<MazeConfig>
 ) {
     let player = (
         PbrBundle {
             mesh: meshes.add(Cuboid::new(consts::PLAYER_LENGTH, consts::PLAYER_HEIGHT, consts::PLAYER_WIDTH)),
             material: materials.add(Color::srgb(0.7,0.1,0.2)),
-            transform: Transform::from_xyz(PLAYER_START_POSITION.x, consts::MAZE_SCALE / 2., PLAYER_START_POSITION.y),
+            transform: Transform::from_xyz(PLAYER_START_POSITION.x, config.maze_scale / 2., PLAYER_START_POSITION.y),
             ..default()
         },
         LogicalPlayer,
@@ -122,6 +138,8 @@ fn setup (
             position: Position{x: PLAYER_START_POSITION.x, y: PLAYER_START_POSITION.y}
         },
         Name::new("Player"),
+        ColliderShape::new(1., 1.),
+        InterpolatedTransform::default(),
         Controller::default(),
         ControllerInput::default(),
     );
@@ -139,7 +157,8 @@ fn setup (
             transform: Transform::from_xyz(0.0, consts::PLAYER_HEIGHT / 4., 0.0),
             ..default()
         },
-        Name::new("PlayerLight")
+        Name::new("PlayerLight"),
+        PlayerLight
     );
 
     let logical_player =     commands.spawn( player ).insert(CameraConfig {
@@ -167,6 +186,20 @@ fn setup (
     });
 }
 
+// the player is spawned before there is a maze, so once there is one move them to its start cell
+fn move_player_to_start(
+    mut player: Query<(&mut Position, &mut Transform), With<LogicalPlayer>>,
+    maze: Res<Maze>,
+    config: Res<MazeConfig>
+) {
+    let (mut player_position, mut player_transform) = player.single_mut();
+    let start = maze.get_layout().get_start();
+    let translation = start.to_vec3_by_scale(config.maze_scale);
+    player_transform.translation.x = translation.x;
+    player_transform.translation.z = translation.z;
+    *player_position = start;
+}
+
 pub fn controller_input(
     key_input: Res<ButtonInput<KeyCode>>,
     mut mouse_motion: EventReader<MouseMotion>,
@@ -197,6 +230,8 @@ pub fn controller_input(
             0.0,
             get_axis(&key_input, KeyCode::ArrowUp, KeyCode::ArrowDown)
         );
+        // sneaking is slower but much quieter
+        input.sneak = key_input.pressed(KeyCode::ShiftLeft) || key_input.pressed(KeyCode::ShiftRight);
     }
 }
 
@@ -226,7 +261,8 @@ pub fn controller_move(
             move_direction /= move_direction.length()
         }
 
-        velocity.set_velocity(Vec2::new(move_direction.x * controller.speed, move_direction.z * controller.speed));
+        let speed = if input.sneak { controller.speed * consts::PLAYER_SNEAK_MULTIPLIER } else { controller.speed };
+        velocity.set_velocity(Vec2::new(move_direction.x * speed, move_direction.z * speed));
     }
 }
 
@@ -250,14 +286,28 @@ pub fn controller_render(
 
 fn check_cell_changed(
     mut player: Query<(&mut Position, &Transform), With<LogicalPlayer>>,
-    mut writer: EventWriter<PlayerCellChangeEvent>
+    mut writer: EventWriter<PlayerCellChangeEvent>,
+    config: Res<MazeConfig>
 ) {
     let (mut player_position, player_transform) = player.single_mut();
-    let new_current_position = Position::get_from_transform(player_transform, consts::MAZE_SCALE);
+    let new_current_position = Position::get_from_transform(player_transform, config.maze_scale);
     if player_position.x != new_current_position.x || player_position.y != new_current_position.y {
         player_position.x = new_current_position.x;
         player_position.y = new_current_position.y;
         // fire event that the position has changed
         writer.send(PlayerCellChangeEvent(new_current_position));
     }
-}
\ No newline at end of file
+}
+
+// every cell the player walks into makes a bit of noise, less the slower they go
+fn make_footstep_noise(
+    mut cell_changes: EventReader<PlayerCellChangeEvent>,
+    player: Query<&Velocity, With<LogicalPlayer>>,
+    mut noise_writer: EventWriter<NoiseEvent>
+) {
+    let velocity = player.single();
+    for cell_change in cell_changes.read() {
+        let loudness = consts::FOOTSTEP_LOUDNESS * (velocity.length() / consts::PLAYER_SPEED).min(1.0);
+        noise_writer.send(NoiseEvent { position: cell_change.0, loudness });
+    }
+}
@@ -1,5 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 use bevy::prelude::*;
 
+pub const SEED_ENV_VAR: &str = "MAZE_SEED";
+
+// Crockford's base32, no I, L, O or U so codes are hard to misread
+const SEED_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const SEED_GROUP_LENGTH: usize = 4;
+
 #[derive(Resource, Deref, DerefMut)]
-pub struct Random(pub ChaCha8Rng);
\ No newline at end of file
+pub struct Random(pub ChaCha8Rng);
+
+/**
+ * The seed every bit of randomness in a game comes from.  Two games with the same seed and MazeConfig get the same maze.
+ * It is shown and entered as a short code like "F3K9-QZ1T", see to_code.
+ * Taken from --seed on the command line, then the MAZE_SEED environment variable, and can be changed in the main menu.
+ * Otherwise it is picked at random.
+ */
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MazeSeed(pub u64);
+
+impl MazeSeed {
+    pub fn random() -> Self {
+        MazeSeed(rand::random())
+    }
+
+    pub fn from_args_or_env(args: &[String]) -> Result<Option<Self>, String> {
+        if let Some(index) = args.iter().position(|arg| arg == "--seed") {
+            let code = args.get(index + 1).ok_or("--seed needs a value")?;
+            return code.parse().map(Some);
+        }
+        match std::env::var(SEED_ENV_VAR) {
+            Ok(code) => code.parse().map(Some),
+            Err(_) => Ok(None)
+        }
+    }
+
+    pub fn create_rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.0)
+    }
+
+    pub fn to_code(&self) -> String {
+        let mut digits = vec![];
+        let mut remaining = self.0;
+        loop {
+            digits.push(SEED_ALPHABET[(remaining % 32) as usize] as char);
+            remaining /= 32;
+            if remaining == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+
+        digits
+            .chunks(SEED_GROUP_LENGTH)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("-")
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        let mut value: u64 = 0;
+        let mut digit_count = 0;
+        for character in code.trim().chars().filter(|character| *character != '-') {
+            let digit = match character.to_ascii_uppercase() {
+                // the usual misreadings
+                'O' => 0,
+                'I' | 'L' => 1,
+                upper => SEED_ALPHABET.iter().position(|letter| *letter as char == upper)
+                    .ok_or(format!("Invalid character '{}' in seed {}", character, code))? as u64
+            };
+            value = value.checked_mul(32)
+                .and_then(|value| value.checked_add(digit))
+                .ok_or(format!("Seed {} is too long", code))?;
+            digit_count += 1;
+        }
+        if digit_count == 0 {
+            return Err(String::from("Seed is empty"));
+        }
+        Ok(MazeSeed(value))
+    }
+}
+
+impl fmt::Display for MazeSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_code())
+    }
+}
+
+impl FromStr for MazeSeed {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        MazeSeed::from_code(code)
+    }
+}